@@ -0,0 +1,247 @@
+use crate::*;
+
+pub(crate) const ONE_HOUR: u64 = 3600000000000;
+
+#[near_bindgen]
+impl Marketplace {
+    /// Cancelar una orden con reembolso total del escrow al empleador.
+    /// Solo ejecutable por el empleador antes de que el profesional la acepte, o dentro
+    /// del periodo de gracia desde la aceptacion mientras no se haya entregado ni pagado ninguna etapa.
+    ///
+    /// #Arguments
+    /// * `order_id`
+    pub fn cancel_order(&mut self, order_id: u64) -> Order {
+        self.assert_order_exists(&order_id);
+
+        let order = self.get_order_by_id(order_id.clone());
+
+        if order.buyer_id != env::predecessor_account_id() {
+            env::panic(b"Only the employeer that buy the service can cancel the order");
+        }
+        if order.status != OrderStatus::Pending {
+            if order.status != OrderStatus::InProgress || order.delivery.is_some() {
+                env::panic(b"The order can't be cancelled once delivered or finished");
+            }
+            if order.milestones.iter().any(|m| m.status != MilestoneStatus::Pending) {
+                env::panic(b"The order can't be cancelled once a milestone was paid");
+            }
+            if order.has_disputed_week() {
+                env::panic(b"The order has a week in dispute");
+            }
+            let grace_end = order.buy_moment + ONE_HOUR * (self.cancel_grace_period as u64);
+            if env::block_timestamp() > grace_end {
+                env::panic(b"The grace period is over, propose a mutual cancellation");
+            }
+        }
+
+        self.internal_settle_milestones(order_id, &order.buyer_id, MilestoneStatus::Refunded);
+        self.internal_finish_order(order_id, OrderStatus::Cancelled)
+    }
+
+
+    /// Proponer la cancelacion de una orden, repartiendo lo que queda en escrow.
+    /// Ejecutable por el empleador o el profesional. Se concreta cuando la otra parte la acepta.
+    /// Una nueva propuesta reemplaza a la anterior.
+    ///
+    /// #Arguments
+    /// * `order_id`
+    /// * `refund_percentage`   - Porcentaje de lo pendiente que se devuelve al empleador, el resto es para el profesional.
+    pub fn propose_cancellation(&mut self, order_id: u64, refund_percentage: u8) -> Order {
+        self.assert_order_exists(&order_id);
+
+        let mut order = self.get_order_by_id(order_id.clone());
+        let sender = env::predecessor_account_id();
+
+        if sender != order.buyer_id && sender != order.creator_id {
+            env::panic(b"Only the employeer or the professional can propose a cancellation");
+        }
+        if !order.is_active() {
+            env::panic(b"The order is already finished or in dispute");
+        }
+        if order.has_disputed_week() {
+            env::panic(b"The order has a week in dispute");
+        }
+        if refund_percentage > 100 {
+            env::panic(b"The refund percentage can't be greater than 100");
+        }
+
+        order.cancellation = Some(CancellationProposal {
+            proposer: sender.clone(),
+            refund_percentage: refund_percentage,
+            proposed_at: env::block_timestamp(),
+        });
+        self.order_by_id.insert(&order_id, &order);
+
+        NearEvent::log_order_cancellation_proposal(
+            order_id.clone().to_string(),
+            sender,
+            refund_percentage.to_string()
+        );
+        order
+    }
+
+
+    /// Aceptar la propuesta de cancelacion de la otra parte.
+    /// Se reparte lo pendiente en escrow segun el porcentaje acordado y se cancela la orden.
+    ///
+    /// #Arguments
+    /// * `order_id`
+    pub fn accept_cancellation(&mut self, order_id: u64) -> Order {
+        self.assert_order_exists(&order_id);
+
+        let mut order = self.get_order_by_id(order_id.clone());
+        let sender = env::predecessor_account_id();
+
+        if sender != order.buyer_id && sender != order.creator_id {
+            env::panic(b"Only the employeer or the professional can accept a cancellation");
+        }
+        if !order.is_active() {
+            env::panic(b"The order is already finished or in dispute");
+        }
+        if order.has_disputed_week() {
+            env::panic(b"The order has a week in dispute");
+        }
+        let proposal = expect_value_found(order.cancellation.clone(), b"There is no cancellation proposal");
+        if proposal.proposer == sender {
+            env::panic(b"The cancellation must be accepted by the other party");
+        }
+
+        let pending = order.pending_amount();
+        let refund = pending * (proposal.refund_percentage as u128) / 100;
+        let payment = pending - refund;
+
+        if refund > 0 {
+            self.internal_release_escrow(order_id, &order.buyer_id, refund);
+        }
+        if payment > 0 {
+            self.internal_release_escrow(order_id, &order.creator_id, payment);
+        }
+
+        for milestone in order.milestones.iter_mut() {
+            if milestone.status == MilestoneStatus::Pending {
+                milestone.status = MilestoneStatus::Cancelled;
+            }
+        }
+        self.order_by_id.insert(&order_id, &order);
+
+        self.internal_finish_order(order_id, OrderStatus::Cancelled)
+    }
+
+
+    /// Modificar las horas del periodo de gracia para cancelar una orden.
+    /// 
+    pub fn set_cancel_grace_period(&mut self, hours: u16) {
+        self.assert_owner();
+        self.cancel_grace_period = hours;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn cancel_within_the_grace_period_refunds_the_employer() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_order(&mut context, &mut contract);
+
+        context.block_timestamp(ONE_HOUR * 23);
+        set_caller(&mut context, 2, 0);
+        let order = contract.cancel_order(order_id);
+
+        assert_eq!(order.status, OrderStatus::Cancelled);
+        assert_eq!(order.milestones[0].status, MilestoneStatus::Refunded);
+        assert_eq!(contract.get_escrow(order_id).released.0, PRICE);
+        assert_eq!(contract.get_escrow_total("near".to_string()).0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "The grace period is over, propose a mutual cancellation")]
+    fn cancel_after_the_grace_period() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_order(&mut context, &mut contract);
+
+        context.block_timestamp(ONE_HOUR * 25);
+        set_caller(&mut context, 2, 0);
+        contract.cancel_order(order_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the employeer that buy the service can cancel the order")]
+    fn only_the_employer_cancels() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_order(&mut context, &mut contract);
+
+        set_caller(&mut context, 1, 0);
+        contract.cancel_order(order_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "The order can't be cancelled once delivered or finished")]
+    fn delivered_orders_cant_be_cancelled() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_order(&mut context, &mut contract);
+        deliver(&mut context, &mut contract, order_id);
+
+        set_caller(&mut context, 2, 0);
+        contract.cancel_order(order_id);
+    }
+
+    #[test]
+    fn mutual_cancellation_settles_the_pending_escrow() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_order(&mut context, &mut contract);
+
+        set_caller(&mut context, 2, 0);
+        contract.propose_cancellation(order_id, 40);
+        set_caller(&mut context, 1, 0);
+        let order = contract.accept_cancellation(order_id);
+
+        assert_eq!(order.status, OrderStatus::Cancelled);
+        assert_eq!(order.milestones[0].status, MilestoneStatus::Cancelled);
+        assert_eq!(contract.get_escrow(order_id).released.0, PRICE);
+        assert_eq!(contract.get_escrow_total("near".to_string()).0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "The cancellation must be accepted by the other party")]
+    fn proposers_cant_accept_their_own_cancellation() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_order(&mut context, &mut contract);
+
+        set_caller(&mut context, 2, 0);
+        contract.propose_cancellation(order_id, 40);
+        contract.accept_cancellation(order_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "There is no cancellation proposal")]
+    fn accept_requires_a_proposal() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_order(&mut context, &mut contract);
+
+        set_caller(&mut context, 1, 0);
+        contract.accept_cancellation(order_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the employeer or the professional can propose a cancellation")]
+    fn only_the_parties_propose_cancellations() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_order(&mut context, &mut contract);
+
+        set_caller(&mut context, 3, 0);
+        contract.propose_cancellation(order_id, 40);
+    }
+
+    #[test]
+    #[should_panic(expected = "The refund percentage can't be greater than 100")]
+    fn refunds_are_capped_at_the_pending_escrow() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_order(&mut context, &mut contract);
+
+        set_caller(&mut context, 2, 0);
+        contract.propose_cancellation(order_id, 101);
+    }
+}
@@ -0,0 +1,128 @@
+use crate::*;
+use std::collections::HashMap;
+
+/// Servicio y paquete a comprar dentro de un carrito.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CartItem {
+    pub service_id: u64,
+    pub package_id: u8,
+}
+
+#[near_bindgen]
+impl Marketplace {
+    /// Comprar varios servicios en una unica transaccion.
+    /// Se verifican todos los servicios antes de cobrar, se debita una sola vez por token
+    /// y se genera una orden por cada uno. Si alguno no es valido falla toda la compra.
+    /// El NEAR adjunto se acredita en la wallet antes de cobrar, lo que sobre queda disponible alli.
+    ///
+    /// #Arguments
+    /// * `items`       - Servicios y paquetes a comprar, hasta 10.
+    /// * `referrer`    - Cuenta que refirio la compra, por defecto quien refirio al empleador.
+    #[payable]
+    pub fn buy_services(&mut self, items: Vec<CartItem>, referrer: Option<ValidAccountId>) -> Vec<Order> {
+        let sender = env::predecessor_account_id();
+        let buyer = self.get_user(string_to_valid_account_id(&sender).clone());
+
+        if items.is_empty() || items.len() > 10 {
+            env::panic(b"Between 1 and 10 services per cart");
+        }
+        let referrer = referrer.map(|id| self.internal_check_referrer(id.into(), &buyer.account_id));
+
+        // Verificar todos los servicios y totalizar lo que hay que cobrar de cada token.
+        let mut purchases: Vec<(Service, u8, Package)> = Vec::new();
+        let mut totals: HashMap<AccountId, Balance> = HashMap::new();
+        for item in items.iter() {
+            if purchases.iter().any(|(service, _, _)| service.id == item.service_id) {
+                env::panic(b"The cart has a repeated service");
+            }
+            let (service, package) = self.internal_check_purchase(item.service_id, item.package_id, &buyer.account_id);
+            *totals.entry(service.metadata.token.clone()).or_insert(0) += package.price.0;
+            purchases.push((service, item.package_id, package));
+        }
+
+        // Cobrar una sola vez por token.
+        self.internal_deposit_attached(&buyer.account_id);
+        for (token, total) in totals.iter() {
+            self.internal_withdraw(token, &buyer.account_id, *total);
+        }
+
+        purchases.iter()
+            .map(|(service, package_id, package)| {
+                self.internal_place_order(service, *package_id, package, &buyer.account_id, referrer.clone())
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+    use crate::test_utils::*;
+
+    fn item(service_id: u64) -> CartItem {
+        CartItem { service_id: service_id, package_id: 0 }
+    }
+
+    #[test]
+    fn carts_debit_each_token_once_and_place_every_order() {
+        let (mut context, mut contract) = setup();
+        let near_service = mint(&mut context, &mut contract);
+        let first_usdc_service = mint_in(&mut context, &mut contract, "usdc.near");
+        let second_usdc_service = mint_in(&mut context, &mut contract, "usdc.near");
+        contract.internal_deposit(&usdc(), &id(2), 3 * PRICE);
+
+        set_caller(&mut context, 2, PRICE);
+        let orders = contract.buy_services(vec![item(near_service), item(first_usdc_service), item(second_usdc_service)], None);
+
+        assert_eq!(orders.len(), 3);
+        assert!(orders.iter().all(|order| order.status == OrderStatus::Pending && order.buyer_id == id(2)));
+        assert_eq!(contract.get_ft_balance_of(usdc(), id(2)), PRICE);
+        assert_eq!(contract.get_escrow_total("near".to_string()).0, PRICE);
+        assert_eq!(contract.get_escrow_total(usdc()).0, 2 * PRICE);
+    }
+
+    #[test]
+    #[should_panic(expected = "Insufficient usdc.near balance in the marketplace wallet")]
+    fn carts_require_the_total_of_each_token() {
+        let (mut context, mut contract) = setup();
+        let first_service = mint_in(&mut context, &mut contract, "usdc.near");
+        let second_service = mint_in(&mut context, &mut contract, "usdc.near");
+        contract.internal_deposit(&usdc(), &id(2), 2 * PRICE - 1);
+
+        set_caller(&mut context, 2, 0);
+        contract.buy_services(vec![item(first_service), item(second_service)], None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Insufficient near balance in the marketplace wallet")]
+    fn carts_require_the_near_total_attached() {
+        let (mut context, mut contract) = setup();
+        let first_service = mint(&mut context, &mut contract);
+        let second_service = mint(&mut context, &mut contract);
+
+        set_caller(&mut context, 2, PRICE);
+        contract.buy_services(vec![item(first_service), item(second_service)], None);
+    }
+
+    #[test]
+    #[should_panic(expected = "The cart has a repeated service")]
+    fn carts_dont_repeat_services() {
+        let (mut context, mut contract) = setup();
+        let service_id = mint(&mut context, &mut contract);
+
+        set_caller(&mut context, 2, 2 * PRICE);
+        contract.buy_services(vec![item(service_id), item(service_id)], None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Can't buy your own service")]
+    fn carts_are_checked_before_charging() {
+        let (mut context, mut contract) = setup();
+        let service_id = mint(&mut context, &mut contract);
+
+        set_caller(&mut context, 1, PRICE);
+        contract.buy_services(vec![item(service_id)], None);
+    }
+}
@@ -0,0 +1,201 @@
+use crate::*;
+
+/// Fecha limite resultante de cambiar la duracion de una orden.
+/// Se parte de la fecha limite vigente para conservar las extensiones por revisiones.
+fn shifted_deadline(order: &Order, new_duration: u16) -> u64 {
+    if new_duration >= order.duration {
+        order.deadline + ONE_DAY * ((new_duration - order.duration) as u64)
+    } else {
+        order.deadline.saturating_sub(ONE_DAY * ((order.duration - new_duration) as u64))
+    }
+}
+
+#[near_bindgen]
+impl Marketplace {
+    /// Proponer una nueva duracion para una orden en curso.
+    /// Ejecutable por el empleador o el profesional. Se aplica cuando la otra parte la acepta.
+    /// Una nueva propuesta reemplaza a la anterior.
+    ///
+    /// #Arguments
+    /// * `order_id`
+    /// * `new_duration`    - Nueva duracion en dias. La fecha limite actual, con las extensiones
+    ///                       por revisiones, se corre la diferencia con la duracion actual.
+    pub fn propose_deadline(&mut self, order_id: u64, new_duration: u16) -> Order {
+        self.assert_order_exists(&order_id);
+
+        let mut order = self.get_order_by_id(order_id.clone());
+        let sender = env::predecessor_account_id();
+
+        if sender != order.buyer_id && sender != order.creator_id {
+            env::panic(b"Only the employeer or the professional can propose a new deadline");
+        }
+        if order.hourly.is_some() || order.subscription {
+            env::panic(b"Hourly contracts and subscriptions keep their deadlines");
+        }
+        if !order.is_active() {
+            env::panic(b"The order is already finished or in dispute");
+        }
+        if new_duration == order.duration {
+            env::panic(b"The new duration must be different from the current one");
+        }
+        let new_deadline = shifted_deadline(&order, new_duration);
+        if new_deadline <= env::block_timestamp() {
+            env::panic(b"The new deadline must be in the future");
+        }
+
+        order.deadline_proposal = Some(DeadlineProposal {
+            proposer: sender.clone(),
+            new_duration: new_duration,
+            proposed_at: env::block_timestamp(),
+        });
+        self.order_by_id.insert(&order_id, &order);
+
+        NearEvent::log_order_deadline_proposal(
+            order_id.clone().to_string(),
+            sender,
+            new_duration.to_string(),
+            new_deadline.to_string()
+        );
+        order
+    }
+
+
+    /// Aceptar la nueva duracion propuesta por la otra parte.
+    /// Actualiza la fecha limite de la orden, y con ella el momento desde el que se puede reclamar el pago.
+    ///
+    /// #Arguments
+    /// * `order_id`
+    pub fn accept_deadline(&mut self, order_id: u64) -> Order {
+        self.assert_order_exists(&order_id);
+
+        let mut order = self.get_order_by_id(order_id.clone());
+        let sender = env::predecessor_account_id();
+
+        if sender != order.buyer_id && sender != order.creator_id {
+            env::panic(b"Only the employeer or the professional can accept a new deadline");
+        }
+        if order.hourly.is_some() || order.subscription {
+            env::panic(b"Hourly contracts and subscriptions keep their deadlines");
+        }
+        if !order.is_active() {
+            env::panic(b"The order is already finished or in dispute");
+        }
+        let proposal = expect_value_found(order.deadline_proposal.clone(), b"There is no deadline proposal");
+        if proposal.proposer == sender {
+            env::panic(b"The new deadline must be accepted by the other party");
+        }
+        let new_deadline = shifted_deadline(&order, proposal.new_duration);
+        if new_deadline <= env::block_timestamp() {
+            env::panic(b"The proposed deadline already passed");
+        }
+
+        // Las etapas pendientes se corren lo mismo que la fecha limite, sin superarla.
+        let old_deadline = order.deadline;
+        for milestone in order.milestones.iter_mut() {
+            if milestone.status == MilestoneStatus::Pending {
+                milestone.deadline = if new_deadline > old_deadline {
+                    milestone.deadline + (new_deadline - old_deadline)
+                } else {
+                    std::cmp::min(milestone.deadline, new_deadline)
+                };
+            }
+        }
+        order.duration = proposal.new_duration;
+        order.deadline = new_deadline;
+        order.deadline_proposal = None;
+        self.order_by_id.insert(&order_id, &order);
+
+        NearEvent::log_service_update_duration(
+            order_id.clone().to_string(),
+            proposal.new_duration.to_string()
+        );
+        order
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn accepted_deadlines_move_the_release_moment() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_order(&mut context, &mut contract);
+
+        set_caller(&mut context, 1, 0);
+        contract.propose_deadline(order_id, 10);
+        set_caller(&mut context, 2, 0);
+        let order = contract.accept_deadline(order_id);
+        assert_eq!(order.duration, 10);
+        assert_eq!(order.deadline, ONE_DAY * 10);
+        assert!(order.deadline_proposal.is_none());
+
+        at_day(&mut context, 1);
+        deliver(&mut context, &mut contract, order_id);
+        at_day(&mut context, 10);
+        set_caller(&mut context, 1, 0);
+        contract.reclaim_service(order_id);
+        assert_eq!(contract.get_order_by_id(order_id).status, OrderStatus::Completed);
+    }
+
+    #[test]
+    #[should_panic(expected = "Insuficient time to reclame the service")]
+    fn extended_deadlines_cant_be_reclaimed_early() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_order(&mut context, &mut contract);
+
+        set_caller(&mut context, 1, 0);
+        contract.propose_deadline(order_id, 10);
+        set_caller(&mut context, 2, 0);
+        contract.accept_deadline(order_id);
+
+        at_day(&mut context, 1);
+        deliver(&mut context, &mut contract, order_id);
+        at_day(&mut context, 6);
+        set_caller(&mut context, 1, 0);
+        contract.reclaim_service(order_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "The new deadline must be accepted by the other party")]
+    fn proposers_cant_accept_their_own_deadline() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_order(&mut context, &mut contract);
+
+        set_caller(&mut context, 1, 0);
+        contract.propose_deadline(order_id, 10);
+        contract.accept_deadline(order_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "There is no deadline proposal")]
+    fn accept_requires_a_deadline_proposal() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_order(&mut context, &mut contract);
+
+        set_caller(&mut context, 2, 0);
+        contract.accept_deadline(order_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "The new deadline must be in the future")]
+    fn deadlines_cant_be_moved_to_the_past() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_order(&mut context, &mut contract);
+
+        at_day(&mut context, 7);
+        set_caller(&mut context, 2, 0);
+        contract.propose_deadline(order_id, 6);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the employeer or the professional can propose a new deadline")]
+    fn only_the_parties_propose_deadlines() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_order(&mut context, &mut contract);
+
+        set_caller(&mut context, 3, 0);
+        contract.propose_deadline(order_id, 10);
+    }
+}
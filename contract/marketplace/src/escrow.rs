@@ -0,0 +1,290 @@
+use crate::*;
+
+/// Fondos bloqueados por la compra de una orden.
+/// Solo se liberan al aprobar, reclamar o finalizar una disputa.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Escrow {
+    pub order_id: u64,
+    pub token: AccountId,
+    // Cantidad bloqueada al momento de la compra.
+    pub amount: U128,
+    // Cantidad ya liberada, ya sea al profesional o de vuelta al empleador.
+    pub released: U128,
+    // Empleador que pago y profesional que debe cobrar.
+    pub payer: AccountId,
+    pub payee: AccountId,
+    pub locked_at: u64,
+}
+
+/// Comparacion del total en escrow de un token con el balance real del contrato.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct EscrowReconciliation {
+    pub token: AccountId,
+    pub escrow_total: U128,
+    // Total disponible en las wallets de los usuarios, tambien en poder del contrato.
+    pub wallet_total: U128,
+    // Comisiones en la tesoreria, tambien en poder del contrato.
+    pub treasury_total: U128,
+    // Comisiones por referidos aun sin cobrar, tambien en poder del contrato.
+    pub referral_total: U128,
+    pub contract_balance: U128,
+    // Si el contrato tiene fondos suficientes para cubrir escrow, wallets, tesoreria y referidos.
+    pub covered: bool,
+}
+
+#[near_bindgen]
+impl Marketplace {
+    /// Bloquear el pago de una orden dentro del Marketplace.
+    /// Los fondos ya deben estar en poder del contrato.
+    pub(crate) fn internal_lock_escrow(&mut self, order: &Order) {
+        let escrow = Escrow {
+            order_id: order.id.clone(),
+            token: order.token.clone(),
+            amount: order.price,
+            released: 0.into(),
+            payer: order.buyer_id.clone(),
+            payee: order.creator_id.clone(),
+            locked_at: env::block_timestamp(),
+        };
+        if self.escrows.insert(&order.id, &escrow).is_some() {
+            env::panic(b"Escrow already exists for the order");
+        }
+
+        let total = self.escrow_totals.get(&order.token).unwrap_or(0);
+        self.escrow_totals.insert(&order.token, &(total + order.price.0));
+
+        NearEvent::log_escrow_lock(
+            order.id.clone().to_string(),
+            escrow.token.clone(),
+            order.price.0.to_string(),
+            escrow.payer.clone(),
+            escrow.payee.clone(),
+        );
+    }
+
+    /// Liberar fondos bloqueados de una orden hacia el beneficiario indicado.
+    /// Solo puede ser el empleador o el profesional de la orden.
+    /// Se acredita en su wallet del Marketplace, desde donde puede hacer withdraw.
+    /// De los pagos al profesional se descuenta la comision de la plataforma.
+    pub(crate) fn internal_release_escrow(&mut self, order_id: u64, beneficiary: &AccountId, amount: Balance) {
+        let mut escrow = expect_value_found(self.escrows.get(&order_id), b"Escrow not found");
+
+        if *beneficiary != escrow.payer && *beneficiary != escrow.payee {
+            env::panic(b"Only the payer or the payee can receive the escrow");
+        }
+        let available = escrow.amount.0 - escrow.released.0;
+        if amount > available {
+            env::panic(b"Insufficient funds in escrow");
+        }
+
+        escrow.released = (escrow.released.0 + amount).into();
+        self.escrows.insert(&order_id, &escrow);
+
+        let total = self.escrow_totals.get(&escrow.token).unwrap_or(0);
+        self.escrow_totals.insert(&escrow.token, &(total - amount));
+
+        NearEvent::log_escrow_release(
+            order_id.clone().to_string(),
+            escrow.token.clone(),
+            amount.to_string(),
+            beneficiary.clone(),
+        );
+
+        let fee = if *beneficiary == escrow.payee {
+            self.internal_collect_fee(order_id, &escrow.token, amount)
+        } else {
+            0
+        };
+        self.internal_deposit(&escrow.token, beneficiary, amount - fee);
+    }
+
+
+    /// Comparar el total bloqueado de un FT con el balance del contrato, consultando `ft_balance_of`.
+    /// Es un metodo de cambio porque requiere la llamada al contrato del token,
+    /// para NEAR se usa la view `get_near_escrow_reconciliation`.
+    ///
+    /// #Arguments
+    /// * `token`   - La cuenta del contrato del token.
+    pub fn reconcile_ft_escrow(&mut self, token: AccountId) -> Promise {
+        if token == "near" {
+            env::panic(b"NEAR is reconciled with get_near_escrow_reconciliation");
+        }
+        if !self.tokens.contains(&token) {
+            env::panic(b"Token not soported");
+        }
+        ext_contract::ft_balance_of(
+            env::current_account_id(),
+            &token, NO_DEPOSIT, BASE_GAS
+        ).then(ext_self::on_reconcile_escrow(
+            token.clone(),
+            &env::current_account_id(), NO_DEPOSIT, BASE_GAS
+        ))
+    }
+
+    /// #Arguments
+    /// * `order_id`
+    pub fn get_escrow(&self, order_id: u64) -> Escrow {
+        expect_value_found(self.escrows.get(&order_id), b"Escrow not found")
+    }
+
+    /// Total bloqueado en escrow de un token.
+    pub fn get_escrow_total(&self, token: AccountId) -> U128 {
+        self.escrow_totals.get(&token).unwrap_or(0).into()
+    }
+
+    /// Total bloqueado en escrow de cada token.
+    pub fn get_escrow_totals(&self) -> Vec<(AccountId, U128)> {
+        self.escrow_totals.iter()
+            .map(|(token, total)| (token, total.into()))
+            .collect()
+    }
+
+    /// Comparar el total de NEAR bloqueado con el balance disponible del contrato,
+    /// descontando lo reservado para el storage.
+    pub fn get_near_escrow_reconciliation(&self) -> EscrowReconciliation {
+        let storage_cost = env::storage_byte_cost() * Balance::from(env::storage_usage());
+        let contract_balance = env::account_balance().saturating_sub(storage_cost);
+        let escrow_total = self.escrow_totals.get(&"near".to_string()).unwrap_or(0);
+        let wallet_total = self.balance_totals.get(&"near".to_string()).unwrap_or(0);
+        let treasury_total = self.treasury.get(&"near".to_string()).unwrap_or(0);
+        let referral_total = self.referral_totals.get(&"near".to_string()).unwrap_or(0);
+
+        EscrowReconciliation {
+            token: "near".to_string(),
+            escrow_total: escrow_total.into(),
+            wallet_total: wallet_total.into(),
+            treasury_total: treasury_total.into(),
+            referral_total: referral_total.into(),
+            contract_balance: contract_balance.into(),
+            covered: contract_balance >= escrow_total + wallet_total + treasury_total + referral_total,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn buy_service_locks_the_price() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_order(&mut context, &mut contract);
+
+        let escrow = contract.get_escrow(order_id);
+        assert_eq!(escrow.amount.0, PRICE);
+        assert_eq!(escrow.released.0, 0);
+        assert_eq!(escrow.payer, id(2));
+        assert_eq!(escrow.payee, id(1));
+        assert_eq!(contract.get_escrow_total("near".to_string()).0, PRICE);
+        assert_eq!(contract.get_near_escrow_reconciliation().escrow_total.0, PRICE);
+    }
+
+    #[test]
+    #[should_panic(expected = "NEAR is reconciled with get_near_escrow_reconciliation")]
+    fn near_is_reconciled_by_the_view() {
+        let (mut context, mut contract) = setup();
+
+        set_caller(&mut context, 3, 0);
+        contract.reconcile_ft_escrow("near".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Token not soported")]
+    fn only_supported_tokens_are_reconciled() {
+        let (mut context, mut contract) = setup();
+
+        set_caller(&mut context, 3, 0);
+        contract.reconcile_ft_escrow("dai.near".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Insufficient near balance in the marketplace wallet")]
+    fn buy_service_requires_the_full_price() {
+        let (mut context, mut contract) = setup();
+        let service_id = mint(&mut context, &mut contract);
+
+        set_caller(&mut context, 2, PRICE - 1);
+        contract.buy_service(service_id, 0, None);
+    }
+
+    #[test]
+    fn approve_releases_the_escrow_to_the_professional() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_order(&mut context, &mut contract);
+
+        set_caller(&mut context, 2, 0);
+        contract.approve_service(order_id, None, None);
+
+        assert_eq!(contract.get_escrow(order_id).released.0, PRICE);
+        assert_eq!(contract.get_escrow_total("near".to_string()).0, 0);
+        assert_eq!(contract.get_order_by_id(order_id).status, OrderStatus::Completed);
+    }
+
+    #[test]
+    #[should_panic(expected = "You aren't the buyer")]
+    fn only_the_buyer_approves() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_order(&mut context, &mut contract);
+
+        set_caller(&mut context, 1, 0);
+        contract.approve_service(order_id, None, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "The order is already finished")]
+    fn approve_requires_an_order_in_progress() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_order(&mut context, &mut contract);
+
+        set_caller(&mut context, 2, 0);
+        contract.approve_service(order_id, None, None);
+        contract.approve_service(order_id, None, None);
+    }
+
+    #[test]
+    fn mediator_returns_the_escrow_to_the_employer() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_order(&mut context, &mut contract);
+        set_on_dispute(&mut contract, order_id);
+
+        set_caller(&mut context, 4, 0);
+        let order = contract.return_service_by_mediator(order_id, true);
+
+        assert_eq!(order.status, OrderStatus::Returned);
+        assert_eq!(contract.get_escrow(order_id).released.0, PRICE);
+        assert_eq!(contract.get_escrow_total("near".to_string()).0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only mediator contract can execute this function")]
+    fn only_the_mediator_resolves_disputes() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_order(&mut context, &mut contract);
+        set_on_dispute(&mut contract, order_id);
+
+        set_caller(&mut context, 2, 0);
+        contract.return_service_by_mediator(order_id, true);
+    }
+
+    #[test]
+    #[should_panic(expected = "Insufficient funds in escrow")]
+    fn escrow_cant_release_more_than_locked() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_order(&mut context, &mut contract);
+
+        contract.internal_release_escrow(order_id, &id(1), PRICE / 2);
+        contract.internal_release_escrow(order_id, &id(1), PRICE / 2 + 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the payer or the payee can receive the escrow")]
+    fn escrow_only_pays_the_parties() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_order(&mut context, &mut contract);
+
+        contract.internal_release_escrow(order_id, &id(3), PRICE);
+    }
+}
@@ -22,13 +22,35 @@ use near_sdk::serde::{Deserialize, Serialize};
 pub enum NearEvent {
     ServiceMint(ServiceMintData),
     ServiceBuy(ServiceBuyData),
+    ServiceOffer(ServiceOfferData),
     ServiceReclaim(ServiceReclaimData),
     ServiceReturn(ServiceReturnData),
     ServiceUpdateMetadata(ServiceUpdateMetadataData),
     ServiceUpdateDuration(ServiceUpdateDurationData),
     ServiceUpdateOnSale(ServiceUpdateOnSaleData),
+    OrderApprove(OrderApproveData),
+    OrderMilestoneApprove(OrderMilestoneApproveData),
+    OrderDelivery(OrderDeliveryData),
+    OrderRevision(OrderRevisionData),
+    OrderUpdateStatus(OrderUpdateStatusData),
+    OrderTimeLog(OrderTimeLogData),
+    OrderWeekUpdate(OrderWeekUpdateData),
+    OrderCancellationProposal(OrderCancellationProposalData),
+    OrderDeadlineProposal(OrderDeadlineProposalData),
+    ReviewNew(ReviewNewData),
+    JobNew(JobNewData),
+    JobProposal(JobProposalData),
+    JobUpdateStatus(JobUpdateStatusData),
+    EscrowLock(EscrowLockData),
+    Rollback(RollbackData),
+    FeeCollect(FeeCollectData),
+    TreasuryWithdraw(TreasuryWithdrawData),
+    ReferralCommission(ReferralCommissionData),
+    ReferralPayout(ReferralPayoutData),
+    EscrowRelease(EscrowReleaseData),
     UserNew(UserNewData),
     UserUpdateRoles(UserUpdateRolesData),
+    UserReputation(UserReputationData),
     UserUpdateDates(UserUpdateDatesData),
 }
 
@@ -41,10 +63,11 @@ pub struct ServiceMintData {
     categories: String,
     price: String,
     duration: String,
+    packages: String,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
-pub struct ServiceBuyData {id: String, buyer: String}
+pub struct ServiceBuyData {id: String, order_id: String, buyer: String, package_id: String}
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ServiceReclaimData {id: String, sender: String}
@@ -60,14 +83,78 @@ pub struct ServiceUpdateMetadataData {
     categories: String,
     price: String,
     duration: String,
+    packages: String,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ServiceOfferData {id: String, creator: String, buyer: String, price: String, duration: String}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ServiceUpdateDurationData {id: String, new_duration: String}
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ServiceUpdateOnSaleData {id: String, on_sale: String}
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct OrderApproveData {id: String, tip_token: Option<String>, tip_amount: Option<String>}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct OrderMilestoneApproveData {id: String, milestone: String, amount: String}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct OrderDeliveryData {id: String, reference: String, hash: String}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct OrderRevisionData {id: String, comment: String, revisions_used: String, deadline: String}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct OrderUpdateStatusData {id: String, status: String}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct OrderTimeLogData {id: String, week: String, hours: String, memo: String}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct OrderWeekUpdateData {id: String, week: String, status: String, amount: String}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct OrderCancellationProposalData {id: String, proposer: String, refund_percentage: String}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct OrderDeadlineProposalData {id: String, proposer: String, duration: String, deadline: String}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct JobNewData {id: String, employer: String, title: String, budget: String, token: String, deadline: String}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct JobProposalData {job_id: String, proposal_id: String, professional: String, price: String, duration: String}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct JobUpdateStatusData {id: String, status: String}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ReviewNewData {id: String, order_id: String, author: String, subject: String, rating: String}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct EscrowLockData {order_id: String, token: String, amount: String, payer: String, payee: String}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct EscrowReleaseData {order_id: String, token: String, amount: String, beneficiary: String}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct FeeCollectData {order_id: String, token: String, amount: String}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TreasuryWithdrawData {token: String, amount: String, receiver: String, by: String, memo: String}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ReferralCommissionData {order_id: String, referrer: String, token: String, amount: String}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ReferralPayoutData {referrer: String, token: String, amount: String}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RollbackData {action: String, account_id: String, token: String, amount: String, reference: String}
+
 // #[skip_serializing_none]
 #[derive(Serialize, Deserialize, Debug)]
 pub struct UserNewData {id: String, employee: bool, data: Option<String>, reputation: String, banned: String}
@@ -75,6 +162,9 @@ pub struct UserNewData {id: String, employee: bool, data: Option<String>, reputa
 #[derive(Serialize, Deserialize, Debug)]
 pub struct UserUpdateRolesData {id: String, remove: bool}
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UserReputationData {id: String, role: String, reputation: String, votes: String}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct UserUpdateDatesData {id: String, data: String}
 
@@ -88,27 +178,34 @@ impl NearEvent {
         description: String,
         categories: String,
         price: String, 
-        duration: String,) 
+        duration: String,
+        packages: String,) 
     {
         let data = ServiceMintData {
-            id, creator, title, description, categories, price, duration}
+            id, creator, title, description, categories, price, duration, packages}
         ;
         NearEvent::ServiceMint(data).log();
     }
 
     // Compra de un servicio.
-    pub fn log_service_buy(id: String,  buyer: String) {
-        let data = ServiceBuyData {id, buyer};
+    pub fn log_service_buy(id: String, order_id: String, buyer: String, package_id: String) {
+        let data = ServiceBuyData {id, order_id, buyer, package_id};
         NearEvent::ServiceBuy(data).log();
     }
 
-    // Reclamo de un servicio por parte del profesional.
+    // Oferta privada de un profesional a un empleador.
+    pub fn log_service_offer(id: String, creator: String, buyer: String, price: String, duration: String) {
+        let data = ServiceOfferData {id, creator, buyer, price, duration};
+        NearEvent::ServiceOffer(data).log();
+    }
+
+    // Reclamo del pago de una orden por parte del profesional.
     pub fn log_service_reclaim(id: String,  sender: String) {
         let data = ServiceReclaimData {id, sender};
         NearEvent::ServiceReclaim(data).log();
     }
 
-    // Retorno de un servicio por parte de un Admin.
+    // Devolucion del pago de una orden por parte de un Admin.
     pub fn log_service_return(id: String,  creator: String) {
         let data = ServiceReturnData {id, creator};
         NearEvent::ServiceReturn(data).log();
@@ -122,15 +219,16 @@ impl NearEvent {
         description: String,
         categories: String,
         price: String, 
-        duration: String,) 
+        duration: String,
+        packages: String,) 
     {
         let data = ServiceUpdateMetadataData {
-            id, title, description, categories, price, duration}
+            id, title, description, categories, price, duration, packages}
         ;
         NearEvent::ServiceUpdateMetadata(data).log();
     }
 
-    // Update de la duracion de un servicio por parte del profesional.
+    // Nueva duracion de una orden acordada entre el empleador y el profesional.
     pub fn log_service_update_duration(id: String,  new_duration: String) {
         let data = ServiceUpdateDurationData {id, new_duration};
         NearEvent::ServiceUpdateDuration(data).log();
@@ -142,6 +240,126 @@ impl NearEvent {
         NearEvent::ServiceUpdateOnSale(data).log();
     }
 
+    // Aprobacion de una orden por parte del empleador, con la propina si la hay.
+    pub fn log_order_approve(id: String, tip_token: Option<String>, tip_amount: Option<String>) {
+        let data = OrderApproveData {id, tip_token, tip_amount};
+        NearEvent::OrderApprove(data).log();
+    }
+
+    // Aprobacion y pago de una etapa de una orden.
+    pub fn log_order_milestone_approve(id: String, milestone: String, amount: String) {
+        let data = OrderMilestoneApproveData {id, milestone, amount};
+        NearEvent::OrderMilestoneApprove(data).log();
+    }
+
+    // Entrega del trabajo de una orden por parte del profesional.
+    pub fn log_order_delivery(id: String, reference: String, hash: String) {
+        let data = OrderDeliveryData {id, reference, hash};
+        NearEvent::OrderDelivery(data).log();
+    }
+
+    // Pedido de revision de una entrega por parte del empleador.
+    pub fn log_order_revision(id: String, comment: String, revisions_used: String, deadline: String) {
+        let data = OrderRevisionData {id, comment, revisions_used, deadline};
+        NearEvent::OrderRevision(data).log();
+    }
+
+    // Cambio de estado de una orden.
+    pub fn log_order_update_status(id: String, status: String) {
+        let data = OrderUpdateStatusData {id, status};
+        NearEvent::OrderUpdateStatus(data).log();
+    }
+
+    // Registro de horas de un contrato por hora.
+    pub fn log_order_time_log(id: String, week: String, hours: String, memo: String) {
+        let data = OrderTimeLogData {id, week, hours, memo};
+        NearEvent::OrderTimeLog(data).log();
+    }
+
+    // Cambio de estado de una semana de un contrato por hora, con lo pagado al profesional.
+    pub fn log_order_week_update(id: String, week: String, status: String, amount: String) {
+        let data = OrderWeekUpdateData {id, week, status, amount};
+        NearEvent::OrderWeekUpdate(data).log();
+    }
+
+    // Propuesta de cancelacion de una orden.
+    pub fn log_order_cancellation_proposal(id: String, proposer: String, refund_percentage: String) {
+        let data = OrderCancellationProposalData {id, proposer, refund_percentage};
+        NearEvent::OrderCancellationProposal(data).log();
+    }
+
+    // Propuesta de una nueva duracion para una orden en curso.
+    pub fn log_order_deadline_proposal(id: String, proposer: String, duration: String, deadline: String) {
+        let data = OrderDeadlineProposalData {id, proposer, duration, deadline};
+        NearEvent::OrderDeadlineProposal(data).log();
+    }
+
+    // Resenia de una orden completada.
+    pub fn log_review_new(id: String, order_id: String, author: String, subject: String, rating: String) {
+        let data = ReviewNewData {id, order_id, author, subject, rating};
+        NearEvent::ReviewNew(data).log();
+    }
+
+    // Publicacion de un trabajo por parte de un empleador.
+    pub fn log_job_new(id: String, employer: String, title: String, budget: String, token: String, deadline: String) {
+        let data = JobNewData {id, employer, title, budget, token, deadline};
+        NearEvent::JobNew(data).log();
+    }
+
+    // Propuesta de un profesional para un trabajo.
+    pub fn log_job_proposal(job_id: String, proposal_id: String, professional: String, price: String, duration: String) {
+        let data = JobProposalData {job_id, proposal_id, professional, price, duration};
+        NearEvent::JobProposal(data).log();
+    }
+
+    // Cambio de estado de un trabajo.
+    pub fn log_job_update_status(id: String, status: String) {
+        let data = JobUpdateStatusData {id, status};
+        NearEvent::JobUpdateStatus(data).log();
+    }
+
+    // Bloqueo de los fondos de una orden.
+    pub fn log_escrow_lock(order_id: String, token: String, amount: String, payer: String, payee: String) {
+        let data = EscrowLockData {order_id, token, amount, payer, payee};
+        NearEvent::EscrowLock(data).log();
+    }
+
+    // Liberacion de fondos bloqueados de una orden.
+    pub fn log_escrow_release(order_id: String, token: String, amount: String, beneficiary: String) {
+        let data = EscrowReleaseData {order_id, token, amount, beneficiary};
+        NearEvent::EscrowRelease(data).log();
+    }
+
+    // Comision de la plataforma cobrada de un pago al profesional.
+    pub fn log_fee_collect(order_id: String, token: String, amount: String) {
+        let data = FeeCollectData {order_id, token, amount};
+        NearEvent::FeeCollect(data).log();
+    }
+
+    // Retiro de fondos de la tesoreria por parte del owner o un admin.
+    pub fn log_treasury_withdraw(token: String, amount: String, receiver: String, by: String, memo: String) {
+        let data = TreasuryWithdrawData {token, amount, receiver, by, memo};
+        NearEvent::TreasuryWithdraw(data).log();
+    }
+
+    // Parte de la comision de un pago que corresponde al referidor de la orden.
+    pub fn log_referral_commission(order_id: String, referrer: String, token: String, amount: String) {
+        let data = ReferralCommissionData {order_id, referrer, token, amount};
+        NearEvent::ReferralCommission(data).log();
+    }
+
+    // Cobro de las comisiones por referidos, acreditadas en la wallet del referidor.
+    pub fn log_referral_payout(referrer: String, token: String, amount: String) {
+        let data = ReferralPayoutData {referrer, token, amount};
+        NearEvent::ReferralPayout(data).log();
+    }
+
+    // Reintegro de fondos por una llamada a otro contrato que fallo.
+    pub fn log_rollback(action: String, account_id: String, token: String, amount: String, reference: String) {
+        let data = RollbackData {action, account_id, token, amount, reference};
+        NearEvent::Rollback(data).log();
+    }
+
 
     // Registro de un nuevo usuario.
     pub fn log_user_new(id: String, employee: bool, data: Option<String>, reputation: String, banned: String) {
@@ -161,6 +379,12 @@ impl NearEvent {
         NearEvent::UserUpdateRoles(data).log();
     }
 
+    // Nueva reputacion de un usuario al recibir un puntaje.
+    pub fn log_user_reputation(id: String, role: String, reputation: String, votes: String) {
+        let data = UserReputationData {id, role, reputation, votes};
+        NearEvent::UserReputation(data).log();
+    }
+
 
     // Funciones internas.
     fn log(&self) {
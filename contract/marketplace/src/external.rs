@@ -2,138 +2,212 @@ use crate::*;
 
 // static DELIMETER: &str = "||";
 
+/// Acciones que se pueden indicar en el `msg` de un `ft_transfer_call`.
+/// Sin `msg` se acreditan los tokens en la wallet de quien los envia.
+#[derive(Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum FtTransferAction {
+    // Acreditar los tokens en la wallet de otra cuenta, o en la propia si no se indica.
+    Deposit { account_id: Option<ValidAccountId> },
+    // Comprar un servicio con los tokens transferidos, opcionalmente indicando quien la refirio.
+    BuyService {
+        service_id: u64,
+        package_id: u8,
+        #[serde(default)]
+        referrer: Option<ValidAccountId>,
+    },
+}
+
 trait FungibleTokenReceiver {
     fn ft_on_transfer(&mut self, sender_id: AccountId, amount: U128, msg: String) -> PromiseOrValue<U128>;
 }
 
 #[near_bindgen]
 impl FungibleTokenReceiver for Marketplace {
+    /// Recibir tokens y ejecutar la accion indicada en `msg`.
+    /// Lo que no se usa se devuelve como resultado para que el contrato del token lo reintegre.
     fn ft_on_transfer(&mut self, sender_id: AccountId, amount: U128, msg: String) -> PromiseOrValue<U128> {
         let ft_contract = env::predecessor_account_id();
         // Verificacion de que el token este dentro de los soportados por Marketplace y 
         // que la fn no sea llamada por cualquier acccount. 
         assert!(self.tokens.contains(&ft_contract), "Token not soported");
-        if ft_contract == self.usdc_contract {
-            let balance = self.usdc_balances.get(&sender_id).unwrap_or(0);
-            self.usdc_balances.insert(&sender_id, &(balance+amount.0));
-        }
-        else if ft_contract == self.jobs_contract {
-            let balance = self.jobs_balances.get(&sender_id).unwrap_or(0);
-            self.jobs_balances.insert(&sender_id, &(balance+amount.0));
-        }
 
-        env::log(&msg.as_bytes());
-        PromiseOrValue::Value(U128(0))
+        let action = if msg.is_empty() {
+            FtTransferAction::Deposit { account_id: None }
+        } else {
+            serde_json::from_str::<FtTransferAction>(&msg).expect("Invalid msg")
+        };
+
+        match action {
+            FtTransferAction::Deposit { account_id } => {
+                let account_id: AccountId = account_id.map(|id| id.into()).unwrap_or(sender_id);
+                self.internal_deposit(&ft_contract, &account_id, amount.0);
+                PromiseOrValue::Value(U128(0))
+            }
+            FtTransferAction::BuyService { service_id, package_id, referrer } => {
+                let buyer = self.get_user(string_to_valid_account_id(&sender_id).clone());
+                let (service, package) = self.internal_check_purchase(service_id, package_id, &buyer.account_id);
+
+                if service.metadata.token != ft_contract {
+                    env::panic(b"The service is priced in another token");
+                }
+                if amount.0 < package.price.0 {
+                    env::panic(b"Insufficient amount to buy the service");
+                }
+
+                let referrer = referrer.map(|id| self.internal_check_referrer(id.into(), &buyer.account_id));
+                self.internal_place_order(&service, package_id, &package, &buyer.account_id, referrer);
+                PromiseOrValue::Value(U128(amount.0 - package.price.0))
+            }
+        }
     }
 }
 
 #[near_bindgen]
 impl Marketplace {
-    /// Callback luego de realizarse el pago que queda inicialmente bloqueado.
-    pub fn on_buy_service(&mut self, service_id: u64) -> Service {
+    /// Callback desde contrato mediador.
+    /// Si la disputa no se pudo crear, se devuelve el deposito a la wallet de quien la inicio
+    /// y se restablece el estado previo de la orden, o la semana disputada de los contratos por hora.
+    /// 
+    pub fn on_new_dispute(&mut self, order_id: u64, applicant: AccountId, deposit: U128, previous_status: OrderStatus) -> bool {
+        if env::predecessor_account_id() != env::current_account_id() {
+            env::panic(b"Only the contract can call its function")
+        }
+        assert_eq!(env::promise_results_count(), 1, "Contract expected a result on the callback");
+        
         match env::promise_result(0) {
             PromiseResult::Successful(_data) => {
-                let mut service = self.get_service_by_id(service_id.clone());
-                let sender = env::signer_account_id();
-                let buyer = self.get_user(string_to_valid_account_id(&sender).clone());
-
-                // Establecer como servicio vendido y no en venta.
-                service.sold = true;
-                service.on_sale = false;
+                let mut order = self.get_order_by_id(order_id.clone());
 
-                // Cambiar propiedad del servicio.
-                service.actual_owner = sender.clone();
-                self.delete_service(&service_id, &service.actual_owner);
-                self.add_service(&service_id, &buyer.account_id);
-
-                // Establecer tiempo de la compra.
-                service.buy_moment = env::block_timestamp();
+                // En los contratos por hora solo se disputa la semana, el resto sigue en curso.
+                if let Some(week) = order.hourly.as_ref().and_then(|terms| terms.disputed_week) {
+                    NearEvent::log_order_week_update(
+                        order_id.clone().to_string(),
+                        week.to_string(),
+                        WeekStatus::OnDispute.to_string(),
+                        "0".to_string()
+                    );
+                    return true;
+                }
 
-                self.service_by_id.insert(&service_id, &service);
+                order.status = OrderStatus::OnDispute;
+                self.order_by_id.insert(&order_id, &order);
 
-                if service.metadata.token == "usdc.fakes.testnet".to_string() {
-                    let actual_balance = self.usdc_balances.get(&sender).unwrap_or(0);
-                    let new_balance = actual_balance - service.metadata.price;
-                    self.usdc_balances.insert(&sender, &new_balance);
+                NearEvent::log_order_update_status(
+                    order_id.clone().to_string(),
+                    order.status.to_string()
+                );
+                true
+            }
+            _ => {
+                let mut order = self.get_order_by_id(order_id.clone());
+                if let Some(terms) = order.hourly.as_mut() {
+                    if let Some(week) = terms.disputed_week.take() {
+                        terms.weeks[week as usize].status = WeekStatus::Contested;
+                    }
                 } else {
-                    let actual_balance = self.jobs_balances.get(&sender).unwrap_or(0);
-                    let new_balance = actual_balance - service.metadata.price;
-                    self.jobs_balances.insert(&sender, &new_balance);
+                    order.status = previous_status;
                 }
-                return service;
+                self.order_by_id.insert(&order_id, &order);
+                if deposit.0 > 0 {
+                    self.internal_deposit(&"near".to_string(), &applicant, deposit.0);
+                }
+
+                NearEvent::log_rollback(
+                    "new_dispute".to_string(),
+                    applicant,
+                    "near".to_string(),
+                    deposit.0.to_string(),
+                    order_id.to_string()
+                );
+                false
             }
-            PromiseResult::Failed => env::panic(b"Callback faild"),
-            PromiseResult::NotReady => env::panic(b"Callback faild"),
-        };
+        }
     }
 
-
-    /// Callback desde contrato mediador.
+    /// Callback con el balance real del contrato en un FT, para compararlo con el escrow.
     /// 
-    pub fn on_new_dispute(&mut self, service_id: u64) {
+    pub fn on_reconcile_escrow(&self, token: AccountId) -> EscrowReconciliation {
         if env::predecessor_account_id() != env::current_account_id() {
             env::panic(b"Only the contract can call its function")
         }
         assert_eq!(env::promise_results_count(), 1, "Contract expected a result on the callback");
-        
-        match env::promise_result(0) {
-            PromiseResult::Successful(_data) => {
-                let mut service = self.get_service_by_id(service_id.clone());
 
-                service.on_dispute = true;
-                self.service_by_id.insert(&service_id, &service);
+        match env::promise_result(0) {
+            PromiseResult::Successful(data) => {
+                let contract_balance: U128 = serde_json::from_slice(&data).expect("Invalid ft_balance_of result");
+                let escrow_total = self.escrow_totals.get(&token).unwrap_or(0);
+                let wallet_total = self.balance_totals.get(&token).unwrap_or(0);
+                let treasury_total = self.treasury.get(&token).unwrap_or(0);
+                let referral_total = self.referral_totals.get(&token).unwrap_or(0);
 
+                return EscrowReconciliation {
+                    token: token,
+                    escrow_total: escrow_total.into(),
+                    wallet_total: wallet_total.into(),
+                    treasury_total: treasury_total.into(),
+                    referral_total: referral_total.into(),
+                    contract_balance: contract_balance,
+                    covered: contract_balance.0 >= escrow_total + wallet_total + treasury_total + referral_total,
+                };
             }
             PromiseResult::Failed => env::panic(b"Callback faild"),
             PromiseResult::NotReady => env::panic(b"Callback faild"),
         };
     }
-
-    /// Callback por reclamo de un servicio por parte del profesional.
+    
+    /// Callback con la metadata de un nuevo token soportado.
     /// 
-    pub fn on_return_service(&mut self, service_id: u64) -> Service {
+    pub fn on_add_token(&mut self, token: AccountId) -> TokenInfo {
         if env::predecessor_account_id() != env::current_account_id() {
             env::panic(b"Only the contract can call its function")
         }
         assert_eq!(env::promise_results_count(), 1, "Contract expected a result on the callback");
-        
-        match env::promise_result(0) {
-            PromiseResult::Successful(_data) => {
-                let mut service = self.get_service_by_id(service_id.clone());
 
-                self.delete_service(&service_id, &service.actual_owner);
-                self.add_service(&service_id, &service.creator_id);
+        match env::promise_result(0) {
+            PromiseResult::Successful(data) => {
+                let metadata: FtMetadata = serde_json::from_slice(&data).expect("Invalid ft_metadata result");
+                let info = TokenInfo {
+                    symbol: metadata.symbol,
+                    decimals: metadata.decimals,
+                };
 
-                // Modificar los datos del servicio.
-                service.actual_owner = service.creator_id.clone();
-                service.on_sale = true;
-                service.buy_moment = 0;
-                service.sold = false;
-                self.service_by_id.insert(&service_id, &service);
+                self.tokens.insert(&token);
+                self.tokens_info.insert(&token, &info);
+                env::log(format!("Token {} added: {} with {} decimals", token, info.symbol, info.decimals).as_bytes());
 
-                return service;
+                return info;
             }
             PromiseResult::Failed => env::panic(b"Callback faild"),
             PromiseResult::NotReady => env::panic(b"Callback faild"),
         };
     }
-    
-    /// Callback por reclamo de un servicio por parte del profesional.
+
+    /// Callback de una transferencia de NEAR o FT desde el Marketplace a un usuario.
+    /// Si fallo, se vuelve a acreditar el monto en su wallet del Marketplace.
     /// 
-    pub fn on_withdraw_ft(&mut self, amount: U128) -> Balance {
+    pub fn on_transfer(&mut self, token: AccountId, account_id: AccountId, amount: U128) -> bool {
         if env::predecessor_account_id() != env::current_account_id() {
             env::panic(b"Only the contract can call its function")
         }
         assert_eq!(env::promise_results_count(), 1, "Contract expected a result on the callback");
         
         match env::promise_result(0) {
-            PromiseResult::Successful(_data) => {
+            PromiseResult::Successful(_data) => true,
+            _ => {
+                self.internal_deposit(&token, &account_id, amount.0);
 
-                return amount.into();
+                NearEvent::log_rollback(
+                    "transfer".to_string(),
+                    account_id,
+                    token,
+                    amount.0.to_string(),
+                    "".to_string()
+                );
+                false
             }
-            PromiseResult::Failed => env::panic(b"Callback faild"),
-            PromiseResult::NotReady => env::panic(b"Callback faild"),
-        };
+        }
     }
 
 }
@@ -146,18 +220,233 @@ pub trait Token {
 }
 #[ext_contract(ext_mediator)]
 pub trait Mediator {
-    fn new_dispute(service_id: u64, applicant: AccountId, accused: AccountId, proves: String, price: u128);
-    fn pay_service(beneficiary: AccountId, amount: U128, token: String);
+    fn new_dispute(order_id: u64, applicant: AccountId, accused: AccountId, proves: String, price: U128);
 }
 #[ext_contract(ext_self)]
 pub trait ExtSelf {
-    fn on_new_dispute(service_id: u64);
-    fn on_transfer_ft(service_id: u64);
-    fn on_buy_service(service_id: u64) -> Service;
-    fn on_return_service(service_id: u64);
+    fn on_new_dispute(order_id: u64, applicant: AccountId, deposit: U128, previous_status: OrderStatus);
+    fn on_transfer(token: AccountId, account_id: AccountId, amount: U128);
+    fn on_reconcile_escrow(token: AccountId) -> EscrowReconciliation;
+    fn on_add_token(token: AccountId) -> TokenInfo;
 }
 #[ext_contract(ext_contract)]
 trait ExtContract {
     fn ft_transfer(&mut self, receiver_id: AccountId, amount: U128, memo: Option<String>);
     fn ft_transfer_call(&mut self, receiver_id: ValidAccountId, amount: U128, memo: Option<String>, msg: String) -> PromiseOrValue<U128>;
-}
\ No newline at end of file
+    fn ft_balance_of(&self, account_id: AccountId) -> U128;
+    fn ft_metadata(&self) -> FtMetadata;
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    /// `ft_transfer_call` de USDC desde la cuenta indicada, retorna lo no usado.
+    fn transfer(context: &mut VMContextBuilder, contract: &mut Marketplace, sender: usize, amount: Balance, msg: &str) -> Balance {
+        testing_env!(context
+            .predecessor_account_id(ValidAccountId::try_from(usdc()).unwrap())
+            .attached_deposit(1)
+            .build());
+        match contract.ft_on_transfer(id(sender), U128(amount), msg.to_string()) {
+            PromiseOrValue::Value(unused) => unused.0,
+            PromiseOrValue::Promise(_) => panic!("Expected a value"),
+        }
+    }
+
+    #[test]
+    fn transfers_without_msg_are_deposited() {
+        let (mut context, mut contract) = setup();
+
+        assert_eq!(transfer(&mut context, &mut contract, 2, PRICE, ""), 0);
+        assert_eq!(contract.get_ft_balance_of(usdc(), id(2)), PRICE);
+    }
+
+    #[test]
+    fn deposits_can_credit_another_account() {
+        let (mut context, mut contract) = setup();
+
+        let msg = format!("{{\"action\":\"deposit\",\"account_id\":\"{}\"}}", id(3));
+        assert_eq!(transfer(&mut context, &mut contract, 2, PRICE, &msg), 0);
+        assert_eq!(contract.get_ft_balance_of(usdc(), id(2)), 0);
+        assert_eq!(contract.get_ft_balance_of(usdc(), id(3)), PRICE);
+    }
+
+    #[test]
+    fn services_are_bought_with_the_transfer_and_the_rest_is_returned() {
+        let (mut context, mut contract) = setup();
+        let service_id = mint_in(&mut context, &mut contract, "usdc.near");
+
+        let msg = format!("{{\"action\":\"buy_service\",\"service_id\":{},\"package_id\":0}}", service_id);
+        assert_eq!(transfer(&mut context, &mut contract, 2, PRICE + 5, &msg), 5);
+
+        let order = contract.get_order_by_id(contract.get_total_orders());
+        assert_eq!(order.buyer_id, id(2));
+        assert_eq!(order.status, OrderStatus::Pending);
+        assert_eq!(contract.get_escrow_total(usdc()).0, PRICE);
+        assert_eq!(contract.get_ft_balance_of(usdc(), id(2)), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Insufficient amount to buy the service")]
+    fn transfers_cover_the_service_price() {
+        let (mut context, mut contract) = setup();
+        let service_id = mint_in(&mut context, &mut contract, "usdc.near");
+
+        let msg = format!("{{\"action\":\"buy_service\",\"service_id\":{},\"package_id\":0}}", service_id);
+        transfer(&mut context, &mut contract, 2, PRICE - 1, &msg);
+    }
+
+    #[test]
+    #[should_panic(expected = "The service is priced in another token")]
+    fn transfers_buy_services_in_the_same_token() {
+        let (mut context, mut contract) = setup();
+        let service_id = mint(&mut context, &mut contract);
+
+        let msg = format!("{{\"action\":\"buy_service\",\"service_id\":{},\"package_id\":0}}", service_id);
+        transfer(&mut context, &mut contract, 2, PRICE, &msg);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid msg")]
+    fn unknown_actions_are_rejected() {
+        let (mut context, mut contract) = setup();
+
+        transfer(&mut context, &mut contract, 2, PRICE, "{\"action\":\"stake\"}");
+    }
+
+    #[test]
+    #[should_panic(expected = "Token not soported")]
+    fn only_supported_tokens_are_received() {
+        let (mut context, mut contract) = setup();
+
+        set_caller(&mut context, 5, 1);
+        contract.ft_on_transfer(id(2), U128(PRICE), "".to_string());
+    }
+
+    #[test]
+    fn created_disputes_put_the_order_on_dispute() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_order(&mut context, &mut contract);
+
+        set_caller(&mut context, 2, 100);
+        contract.reclaim_dispute(order_id, "The delivery is empty".to_string());
+        set_callback(&mut context, PromiseResult::Successful(vec![]));
+        assert!(contract.on_new_dispute(order_id, id(2), U128(100), OrderStatus::InProgress));
+
+        assert_eq!(contract.get_order_by_id(order_id).status, OrderStatus::OnDispute);
+        assert_eq!(near_balance(&contract, 2), 0);
+    }
+
+    #[test]
+    fn failed_disputes_refund_the_deposit_to_the_wallet() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_order(&mut context, &mut contract);
+
+        set_caller(&mut context, 2, 100);
+        contract.reclaim_dispute(order_id, "The delivery is empty".to_string());
+        set_callback(&mut context, PromiseResult::Failed);
+        assert!(!contract.on_new_dispute(order_id, id(2), U128(100), OrderStatus::InProgress));
+
+        assert_eq!(contract.get_order_by_id(order_id).status, OrderStatus::InProgress);
+        assert_eq!(near_balance(&contract, 2), 100);
+    }
+
+    #[test]
+    fn failed_disputes_restore_the_delivered_status() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_order(&mut context, &mut contract);
+        deliver(&mut context, &mut contract, order_id);
+
+        set_caller(&mut context, 2, 100);
+        contract.reclaim_dispute(order_id, "The delivery is empty".to_string());
+        assert_eq!(contract.get_order_by_id(order_id).status, OrderStatus::DisputeRequested);
+        set_callback(&mut context, PromiseResult::Failed);
+        contract.on_new_dispute(order_id, id(2), U128(100), OrderStatus::Delivered);
+
+        assert_eq!(contract.get_order_by_id(order_id).status, OrderStatus::Delivered);
+    }
+
+    #[test]
+    #[should_panic(expected = "You already have requested a dispute for this order")]
+    fn requested_disputes_block_the_approval() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_order(&mut context, &mut contract);
+
+        set_caller(&mut context, 2, 100);
+        contract.reclaim_dispute(order_id, "The delivery is empty".to_string());
+        set_caller(&mut context, 2, 0);
+        contract.approve_service(order_id, None, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "The order can't be cancelled once delivered or finished")]
+    fn requested_disputes_block_the_cancellation() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_order(&mut context, &mut contract);
+
+        set_caller(&mut context, 2, 100);
+        contract.reclaim_dispute(order_id, "The delivery is empty".to_string());
+        set_caller(&mut context, 2, 0);
+        contract.cancel_order(order_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "The order is already finished or in dispute")]
+    fn requested_disputes_block_mutual_cancellations() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_order(&mut context, &mut contract);
+
+        set_caller(&mut context, 1, 0);
+        contract.propose_cancellation(order_id, 50);
+        set_caller(&mut context, 2, 100);
+        contract.reclaim_dispute(order_id, "The delivery is empty".to_string());
+        set_caller(&mut context, 2, 0);
+        contract.accept_cancellation(order_id);
+    }
+
+    #[test]
+    fn failed_week_disputes_reopen_the_contested_week() {
+        let (mut context, mut contract) = setup();
+        contract.internal_deposit(&usdc(), &id(2), 1_000);
+        set_caller(&mut context, 2, 0);
+        let order_id = contract.create_hourly_contract(accounts(1), "Backend maintenance".to_string(), usdc(), U128(100), 10, 1).id;
+        accept(&mut context, &mut contract, order_id);
+        contract.log_time(order_id, 0, 5, "API fixes".to_string());
+        set_caller(&mut context, 2, 0);
+        contract.contest_week(order_id, 0);
+
+        set_caller(&mut context, 1, 100);
+        contract.dispute_week(order_id, 0, "Time tracker export".to_string());
+        assert_eq!(contract.internal_week_status(&contract.get_order_by_id(order_id), 0), WeekStatus::OnDispute);
+        set_callback(&mut context, PromiseResult::Failed);
+        contract.on_new_dispute(order_id, id(1), U128(100), OrderStatus::InProgress);
+
+        let order = contract.get_order_by_id(order_id);
+        assert_eq!(contract.internal_week_status(&order, 0), WeekStatus::Contested);
+        assert_eq!(order.hourly.unwrap().disputed_week, None);
+        assert_eq!(near_balance(&contract, 1), 100);
+    }
+
+    #[test]
+    fn failed_transfers_are_credited_back() {
+        let (mut context, mut contract) = setup();
+        set_caller(&mut context, 2, PRICE);
+        contract.deposit_near(None);
+
+        set_caller(&mut context, 2, 0);
+        contract.withdraw_near(U128(PRICE));
+        assert_eq!(near_balance(&contract, 2), 0);
+        set_callback(&mut context, PromiseResult::Failed);
+        assert!(!contract.on_transfer("near".to_string(), id(2), U128(PRICE)));
+        assert_eq!(near_balance(&contract, 2), PRICE);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the contract can call its function")]
+    fn only_the_contract_confirms_transfers() {
+        let (mut context, mut contract) = setup();
+
+        set_caller(&mut context, 2, 0);
+        contract.on_transfer("near".to_string(), id(2), U128(PRICE));
+    }
+}
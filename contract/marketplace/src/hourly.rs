@@ -0,0 +1,552 @@
+use crate::*;
+
+const ONE_WEEK: u64 = 7 * ONE_DAY;
+
+#[near_bindgen]
+impl Marketplace {
+    /// Crear un contrato por hora con un profesional, financiando el tope semanal de horas
+    /// de todas las semanas desde la wallet del Marketplace.
+    /// Queda pendiente de aceptacion del profesional como cualquier orden.
+    ///
+    /// #Arguments
+    /// * `professional_id` - La cuenta de mainnet/testnet del profesional.
+    /// * `title`           - Descripcion del trabajo.
+    /// * `token`           - "near" o la cuenta del contrato del token.
+    /// * `rate`            - Precio por hora en la unidad minima del token.
+    /// * `weekly_cap`      - Maximo de horas por semana.
+    /// * `weeks`           - Cantidad de semanas del contrato.
+    pub fn create_hourly_contract(
+        &mut self,
+        professional_id: ValidAccountId,
+        title: String,
+        token: AccountId,
+        rate: U128,
+        weekly_cap: u16,
+        weeks: u8,
+    ) -> Order {
+        let sender = env::predecessor_account_id();
+        let professional_id: AccountId = professional_id.into();
+
+        let buyer = self.get_user(string_to_valid_account_id(&sender).clone());
+        let professional = self.get_user(string_to_valid_account_id(&professional_id).clone());
+        if !professional.is_employee {
+            env::panic(b"Only professionals can be hired by the hour");
+        }
+        if professional.account_id == buyer.account_id {
+            env::panic(b"Can't hire yourself");
+        }
+        if title.len() < 10 || title.len() > 58 {
+            env::panic(b"Title between 10 and 58 characters");
+        }
+        if !self.tokens.contains(&token) {
+            env::panic(b"Token not soported");
+        }
+        if rate.0 == 0 {
+            env::panic(b"The rate must be greater than zero");
+        }
+        if weekly_cap < 1 || weekly_cap > 168 {
+            env::panic(b"Between 1 and 168 hours per week");
+        }
+        if weeks < 1 || weeks > 52 {
+            env::panic(b"Between 1 and 52 weeks");
+        }
+
+        // Se bloquea el tope de horas de cada semana, lo no trabajado se devuelve al aprobarla.
+        let weekly_amount = rate.0 * weekly_cap as u128;
+        let amount = weekly_amount * weeks as u128;
+        self.internal_withdraw(&token, &buyer.account_id, amount);
+
+        // Cada semana es una etapa, sus fechas limite se fijan al aceptarse la orden.
+        let milestones: Vec<Milestone> = (1..=weeks).map(|week| Milestone {
+            description: format!("Week {}", week),
+            amount: weekly_amount.into(),
+            duration: 7 * week as u16,
+            deadline: 0,
+            status: MilestoneStatus::Pending,
+        }).collect();
+
+        let order = self.internal_insert_order(Order {
+            id: 0,
+            service_id: None,
+            package_id: 0,
+            job_id: None,
+            buyer_id: buyer.account_id.clone(),
+            creator_id: professional.account_id.clone(),
+            price: amount.into(),
+            token: token,
+            duration: 7 * weeks as u16,
+            created_at: env::block_timestamp(),
+            buy_moment: 0,
+            deadline: 0,
+            finish_moment: None,
+            status: OrderStatus::Pending,
+            milestones: milestones,
+            delivery: None,
+            revisions: 0,
+            revision_requests: Vec::new(),
+            cancellation: None,
+            deadline_proposal: None,
+            subscription: false,
+            hourly: Some(HourlyTerms {
+                title: title,
+                rate: rate,
+                weekly_cap: weekly_cap,
+                weeks: (0..weeks).map(|_| WorkWeek {
+                    hours: 0,
+                    logs: Vec::new(),
+                    status: WeekStatus::Open,
+                }).collect(),
+                disputed_week: None,
+            }),
+            referrer: None,
+            tip: None,
+        });
+        self.internal_lock_escrow(&order);
+
+        NearEvent::log_order_update_status(order.id.to_string(), order.status.to_string());
+        order
+    }
+
+
+    /// Registrar horas trabajadas en una semana ya iniciada, sin superar el tope semanal.
+    /// Solo ejecutable por el profesional.
+    ///
+    /// #Arguments
+    /// * `order_id`
+    /// * `week`    - Indice de la semana, desde 0.
+    /// * `hours`
+    /// * `memo`    - Detalle de lo realizado o hash del registro.
+    pub fn log_time(&mut self, order_id: u64, week: u8, hours: u16, memo: String) -> Order {
+        self.assert_order_exists(&order_id);
+
+        let mut order = self.get_order_by_id(order_id.clone());
+
+        if order.creator_id != env::predecessor_account_id() {
+            env::panic(b"Only the corresponding professional can log time");
+        }
+        if order.status != OrderStatus::InProgress {
+            env::panic(b"The order isn't in progress");
+        }
+        if hours == 0 {
+            env::panic(b"Min 1 hour");
+        }
+        if memo.len() > 180 {
+            env::panic(b"Memo max 180 characters");
+        }
+        let week_end = expect_value_found(order.milestones.get(week as usize), b"The indicated week doesn't exist").deadline;
+        if env::block_timestamp() < week_end - ONE_WEEK {
+            env::panic(b"The week hasn't started yet");
+        }
+
+        let terms = expect_value_found(order.hourly.as_mut(), b"The order isn't an hourly contract");
+        let cap = terms.weekly_cap;
+        let work_week = &mut terms.weeks[week as usize];
+        if work_week.status != WeekStatus::Open {
+            env::panic(b"The week is already closed");
+        }
+        if work_week.logs.len() >= 50 {
+            env::panic(b"Max 50 time logs per week");
+        }
+        if work_week.hours + hours > cap {
+            env::panic(b"The hours exceed the weekly cap");
+        }
+        work_week.hours += hours;
+        work_week.logs.push(TimeLog {
+            hours: hours,
+            memo: memo.clone(),
+            logged_at: env::block_timestamp(),
+        });
+        self.order_by_id.insert(&order_id, &order);
+
+        NearEvent::log_order_time_log(
+            order_id.to_string(),
+            week.to_string(),
+            hours.to_string(),
+            memo
+        );
+        order
+    }
+
+
+    /// Aprobar las horas de una semana, pagandolas al profesional y devolviendo lo no trabajado.
+    /// Solo ejecutable por el empleador.
+    ///
+    /// #Arguments
+    /// * `order_id`
+    /// * `week`    - Indice de la semana, desde 0.
+    pub fn approve_week(&mut self, order_id: u64, week: u8) -> Order {
+        self.assert_order_exists(&order_id);
+
+        let order = self.get_order_by_id(order_id.clone());
+
+        if order.buyer_id != env::predecessor_account_id() {
+            env::panic(b"Only the employeer can approve a week");
+        }
+        if !order.is_active() {
+            env::panic(b"The order isn't in progress");
+        }
+        let status = self.internal_week_status(&order, week);
+        if status != WeekStatus::Open && status != WeekStatus::Contested {
+            env::panic(b"The week is already closed");
+        }
+
+        self.internal_settle_week(order_id, week, true)
+    }
+
+
+    /// Objetar las horas de una semana. El profesional puede llevarla al mediador.
+    /// Solo ejecutable por el empleador.
+    ///
+    /// #Arguments
+    /// * `order_id`
+    /// * `week`    - Indice de la semana, desde 0.
+    pub fn contest_week(&mut self, order_id: u64, week: u8) -> Order {
+        self.assert_order_exists(&order_id);
+
+        let mut order = self.get_order_by_id(order_id.clone());
+
+        if order.buyer_id != env::predecessor_account_id() {
+            env::panic(b"Only the employeer can contest a week");
+        }
+        if !order.is_active() {
+            env::panic(b"The order isn't in progress");
+        }
+        if self.internal_week_status(&order, week) != WeekStatus::Open {
+            env::panic(b"The week isn't open");
+        }
+
+        let terms = order.hourly.as_mut().unwrap();
+        if terms.weeks[week as usize].hours == 0 {
+            env::panic(b"There are no hours to contest");
+        }
+        terms.weeks[week as usize].status = WeekStatus::Contested;
+        self.order_by_id.insert(&order_id, &order);
+
+        NearEvent::log_order_week_update(
+            order_id.to_string(),
+            week.to_string(),
+            WeekStatus::Contested.to_string(),
+            "0".to_string()
+        );
+        order
+    }
+
+
+    /// Cobrar las horas de una semana que el empleador no reviso.
+    /// Solo ejecutable por el profesional una vez terminada la semana y el periodo de revision.
+    ///
+    /// #Arguments
+    /// * `order_id`
+    /// * `week`    - Indice de la semana, desde 0.
+    pub fn claim_week(&mut self, order_id: u64, week: u8) -> Order {
+        self.assert_order_exists(&order_id);
+
+        let order = self.get_order_by_id(order_id.clone());
+
+        if order.creator_id != env::predecessor_account_id() {
+            env::panic(b"Only the corresponding professional can claim a week");
+        }
+        if !order.is_active() {
+            env::panic(b"The order isn't in progress");
+        }
+        if self.internal_week_status(&order, week) != WeekStatus::Open {
+            env::panic(b"The week isn't open");
+        }
+        let review_end = order.milestones[week as usize].deadline + ONE_DAY * (self.review_period as u64);
+        if env::block_timestamp() < review_end {
+            env::panic(b"The employeer still has time to review the week");
+        }
+
+        self.internal_settle_week(order_id, week, true)
+    }
+
+
+    /// Llevar una semana objetada al mediador, disputando solo su monto.
+    /// La orden sigue en curso y el resto de las semanas se pueden registrar, aprobar y cobrar.
+    /// Solo ejecutable por el profesional.
+    ///
+    /// #Arguments
+    /// * `order_id`
+    /// * `week`    - Indice de la semana, desde 0.
+    /// * `proves`  - Pruebas de las horas trabajadas.
+    #[payable]
+    pub fn dispute_week(&mut self, order_id: u64, week: u8, proves: String) {
+        let user_id = string_to_valid_account_id(&env::predecessor_account_id());
+        if self.get_user(user_id).banned == true {
+            env::panic(b"You are already banned for fraudulent disputes");
+        }
+        self.assert_order_exists(&order_id);
+
+        let mut order = self.get_order_by_id(order_id.clone());
+
+        if order.creator_id != env::signer_account_id() {
+            env::panic(b"Only the corresponding professional can dispute a week");
+        }
+        if !order.is_active() {
+            env::panic(b"The order isn't in progress");
+        }
+        if self.internal_week_status(&order, week) != WeekStatus::Contested {
+            env::panic(b"Only contested weeks can be disputed");
+        }
+        if order.has_disputed_week() {
+            env::panic(b"Another week is already in dispute");
+        }
+
+        let terms = order.hourly.as_mut().unwrap();
+        let amount = terms.rate.0 * terms.weeks[week as usize].hours as u128;
+        terms.weeks[week as usize].status = WeekStatus::OnDispute;
+        terms.disputed_week = Some(week);
+        self.order_by_id.insert(&order_id, &order);
+
+        let _res = ext_mediator::new_dispute(
+            order_id,
+            env::signer_account_id(),
+            order.buyer_id.clone(),
+            proves,
+            amount.into(),
+            &self.contract_me,
+            env::attached_deposit(),
+            BASE_GAS,
+        ).then(ext_self::on_new_dispute(
+            order_id,
+            env::signer_account_id(),
+            env::attached_deposit().into(),
+            order.status.clone(),
+            &env::current_account_id(),
+            NO_DEPOSIT,
+            BASE_GAS,
+        ));
+    }
+
+
+    /// Estado de una semana de un contrato por hora.
+    pub(crate) fn internal_week_status(&self, order: &Order, week: u8) -> WeekStatus {
+        let terms = expect_value_found(order.hourly.as_ref(), b"The order isn't an hourly contract");
+        expect_value_found(terms.weeks.get(week as usize), b"The indicated week doesn't exist").status.clone()
+    }
+
+    /// Cerrar una semana, pagando las horas registradas al profesional si corresponde
+    /// y devolviendo el resto al empleador. Al cerrarse la ultima se completa la orden.
+    pub(crate) fn internal_settle_week(&mut self, order_id: u64, week: u8, pay_hours: bool) -> Order {
+        let mut order = self.get_order_by_id(order_id.clone());
+        let index = week as usize;
+
+        let terms = order.hourly.as_mut().unwrap();
+        let payment = if pay_hours { terms.rate.0 * terms.weeks[index].hours as u128 } else { 0 };
+        terms.weeks[index].status = WeekStatus::Paid;
+        if terms.disputed_week == Some(week) {
+            terms.disputed_week = None;
+        }
+
+        let refund = order.milestones[index].amount.0 - payment;
+        if payment > 0 {
+            self.internal_release_escrow(order_id, &order.creator_id, payment);
+        }
+        if refund > 0 {
+            self.internal_release_escrow(order_id, &order.buyer_id, refund);
+        }
+        order.milestones[index].status = if payment > 0 { MilestoneStatus::Released } else { MilestoneStatus::Refunded };
+        self.order_by_id.insert(&order_id, &order);
+
+        NearEvent::log_order_week_update(
+            order_id.to_string(),
+            week.to_string(),
+            WeekStatus::Paid.to_string(),
+            payment.to_string()
+        );
+
+        if order.pending_amount() == 0 {
+            return self.internal_finish_order(order_id, OrderStatus::Completed);
+        }
+        order
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use crate::test_utils::*;
+
+    /// Contrato por hora ya aceptado, de 2 semanas a 100 la hora con un tope de 10 horas semanales.
+    fn place_hourly_contract(context: &mut VMContextBuilder, contract: &mut Marketplace) -> u64 {
+        contract.internal_deposit(&usdc(), &id(2), 2_000);
+        set_caller(context, 2, 0);
+        let order = contract.create_hourly_contract(
+            accounts(1),
+            "Backend maintenance".to_string(),
+            usdc(),
+            U128(100),
+            10,
+            2,
+        );
+        accept(context, contract, order.id);
+        order.id
+    }
+
+    #[test]
+    fn approved_weeks_pay_the_hours_and_refund_the_rest() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_hourly_contract(&mut context, &mut contract);
+        assert_eq!(contract.get_escrow_total(usdc()).0, 2_000);
+
+        set_caller(&mut context, 1, 0);
+        contract.log_time(order_id, 0, 6, "API fixes".to_string());
+        set_caller(&mut context, 2, 0);
+        let order = contract.approve_week(order_id, 0);
+
+        assert_eq!(order.status, OrderStatus::InProgress);
+        assert_eq!(order.hourly.unwrap().weeks[0].status, WeekStatus::Paid);
+        assert_eq!(order.milestones[0].status, MilestoneStatus::Released);
+        assert_eq!(contract.get_ft_balance_of(usdc(), id(1)), 600);
+        assert_eq!(contract.get_ft_balance_of(usdc(), id(2)), 400);
+        assert_eq!(contract.get_escrow_total(usdc()).0, 1_000);
+    }
+
+    #[test]
+    fn settling_the_last_week_completes_the_order() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_hourly_contract(&mut context, &mut contract);
+
+        set_caller(&mut context, 2, 0);
+        let order = contract.approve_week(order_id, 0);
+        assert_eq!(order.milestones[0].status, MilestoneStatus::Refunded);
+
+        let order = contract.approve_week(order_id, 1);
+        assert_eq!(order.status, OrderStatus::Completed);
+        assert_eq!(contract.get_ft_balance_of(usdc(), id(2)), 2_000);
+        assert_eq!(contract.get_escrow_total(usdc()).0, 0);
+    }
+
+    #[test]
+    fn unreviewed_weeks_are_claimed_after_the_review_period() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_hourly_contract(&mut context, &mut contract);
+
+        set_caller(&mut context, 1, 0);
+        contract.log_time(order_id, 0, 4, "Database migration".to_string());
+
+        at_day(&mut context, 7 + contract.review_period as u64);
+        set_caller(&mut context, 1, 0);
+        let order = contract.claim_week(order_id, 0);
+
+        assert_eq!(order.hourly.unwrap().weeks[0].status, WeekStatus::Paid);
+        assert_eq!(contract.get_ft_balance_of(usdc(), id(1)), 400);
+        assert_eq!(contract.get_ft_balance_of(usdc(), id(2)), 600);
+    }
+
+    #[test]
+    #[should_panic(expected = "The employeer still has time to review the week")]
+    fn weeks_arent_claimed_during_the_review() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_hourly_contract(&mut context, &mut contract);
+
+        set_caller(&mut context, 1, 0);
+        contract.log_time(order_id, 0, 4, "Database migration".to_string());
+
+        at_day(&mut context, 8);
+        set_caller(&mut context, 1, 0);
+        contract.claim_week(order_id, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "The hours exceed the weekly cap")]
+    fn log_time_respects_the_weekly_cap() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_hourly_contract(&mut context, &mut contract);
+
+        set_caller(&mut context, 1, 0);
+        contract.log_time(order_id, 0, 8, "API fixes".to_string());
+        contract.log_time(order_id, 0, 3, "API fixes".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the corresponding professional can log time")]
+    fn only_the_professional_logs_time() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_hourly_contract(&mut context, &mut contract);
+
+        set_caller(&mut context, 2, 0);
+        contract.log_time(order_id, 0, 2, "API fixes".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the employeer can approve a week")]
+    fn only_the_employer_approves_weeks() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_hourly_contract(&mut context, &mut contract);
+
+        set_caller(&mut context, 1, 0);
+        contract.approve_week(order_id, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Insufficient usdc.near balance in the marketplace wallet")]
+    fn hourly_contracts_fund_every_week() {
+        let (mut context, mut contract) = setup();
+        contract.internal_deposit(&usdc(), &id(2), 1_999);
+
+        set_caller(&mut context, 2, 0);
+        contract.create_hourly_contract(accounts(1), "Backend maintenance".to_string(), usdc(), U128(100), 10, 2);
+    }
+
+    /// Semana 0 objetada por el empleador y llevada al mediador por el profesional.
+    fn dispute_first_week(context: &mut VMContextBuilder, contract: &mut Marketplace, order_id: u64) {
+        set_caller(context, 1, 0);
+        contract.log_time(order_id, 0, 5, "API fixes".to_string());
+        set_caller(context, 2, 0);
+        contract.contest_week(order_id, 0);
+        set_caller(context, 1, 100);
+        contract.dispute_week(order_id, 0, "Time tracker export".to_string());
+        set_callback(context, PromiseResult::Successful(vec![]));
+        contract.on_new_dispute(order_id, id(1), U128(100), OrderStatus::InProgress);
+    }
+
+    #[test]
+    fn other_weeks_go_on_while_one_is_disputed() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_hourly_contract(&mut context, &mut contract);
+        dispute_first_week(&mut context, &mut contract, order_id);
+        assert_eq!(contract.get_order_by_id(order_id).status, OrderStatus::InProgress);
+
+        at_day(&mut context, 8);
+        set_caller(&mut context, 1, 0);
+        contract.log_time(order_id, 1, 3, "Deploy scripts".to_string());
+        set_caller(&mut context, 2, 0);
+        let order = contract.approve_week(order_id, 1);
+
+        assert_eq!(order.status, OrderStatus::InProgress);
+        assert_eq!(contract.internal_week_status(&order, 0), WeekStatus::OnDispute);
+        assert_eq!(contract.get_ft_balance_of(usdc(), id(1)), 300);
+    }
+
+    #[test]
+    #[should_panic(expected = "The order has a week in dispute")]
+    fn orders_with_a_disputed_week_cant_be_cancelled() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_hourly_contract(&mut context, &mut contract);
+        dispute_first_week(&mut context, &mut contract, order_id);
+
+        set_caller(&mut context, 2, 0);
+        contract.propose_cancellation(order_id, 50);
+    }
+
+    #[test]
+    #[should_panic(expected = "Hourly contracts are disputed by week")]
+    fn hourly_contracts_arent_disputed_as_a_whole() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_hourly_contract(&mut context, &mut contract);
+
+        set_caller(&mut context, 2, 100);
+        contract.reclaim_dispute(order_id, "Nothing was done".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Hourly contracts and subscriptions keep their deadlines")]
+    fn hourly_contracts_keep_their_deadline() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_hourly_contract(&mut context, &mut contract);
+
+        set_caller(&mut context, 1, 0);
+        contract.propose_deadline(order_id, 21);
+    }
+}
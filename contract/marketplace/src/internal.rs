@@ -14,6 +14,48 @@ pub(crate) fn unique_prefix(account_id: &AccountId) -> Vec<u8> {
     prefix
 }
 
+pub(crate) fn unique_order_prefix(account_id: &AccountId) -> Vec<u8> {
+    let mut prefix = Vec::with_capacity(33);
+    prefix.push(b'r');
+    prefix.extend(env::sha256(account_id.as_bytes()));
+    prefix
+}
+
+pub(crate) fn unique_jobs_prefix(account_id: &AccountId) -> Vec<u8> {
+    let mut prefix = Vec::with_capacity(33);
+    prefix.push(b'q');
+    prefix.extend(env::sha256(account_id.as_bytes()));
+    prefix
+}
+
+pub(crate) fn unique_job_prefix(job_id: &u64) -> Vec<u8> {
+    let mut prefix = Vec::with_capacity(33);
+    prefix.push(b't');
+    prefix.extend(env::sha256(&job_id.to_le_bytes()));
+    prefix
+}
+
+pub(crate) fn unique_offer_prefix(account_id: &AccountId) -> Vec<u8> {
+    let mut prefix = Vec::with_capacity(33);
+    prefix.push(b'u');
+    prefix.extend(env::sha256(account_id.as_bytes()));
+    prefix
+}
+
+pub(crate) fn unique_service_reviews_prefix(service_id: &u64) -> Vec<u8> {
+    let mut prefix = Vec::with_capacity(33);
+    prefix.push(b'F');
+    prefix.extend(env::sha256(&service_id.to_le_bytes()));
+    prefix
+}
+
+pub(crate) fn unique_account_reviews_prefix(account_id: &AccountId) -> Vec<u8> {
+    let mut prefix = Vec::with_capacity(33);
+    prefix.push(b'G');
+    prefix.extend(env::sha256(account_id.as_bytes()));
+    prefix
+}
+
 pub(crate) fn deposit_refund(storage_used: u64) {
     let required_cost = STORAGE_PRICE_PER_BYTE * Balance::from(storage_used);
     let attached_deposit = env::attached_deposit();
@@ -45,6 +87,99 @@ pub(crate) fn deposit_refund_to(storage_used: u64, to: AccountId) {
     }
 }
 
+/// Verificar el titulo, la descripcion y las categorias de un servicio.
+pub(crate) fn assert_valid_service_metadata(metadata: &ServiceMetadata) {
+    if metadata.title.len() > 58 {
+        env::panic(b"Title max 58 characters");
+    }
+    else if metadata.title.len() < 10 {
+        env::panic(b"Title min 10 characters");
+    }
+
+    if metadata.description.len() > 180 {
+        env::panic(b"Description max 180 characters");
+    }
+    else if metadata.description.len() < 10 {
+        env::panic(b"Description min 10 characters");
+    }
+
+    let categories: Vec<String> = serde_json::from_str(&metadata.categories).unwrap();
+    if categories.len() > 15 {
+        env::panic(b"Max 15 categories");
+    }
+    else if categories.len() < 1 {
+        env::panic(b"Min 1 categories");
+    }
+}
+
+/// Verificar los terminos del servicio y de cada uno de sus paquetes.
+pub(crate) fn assert_valid_service_terms(metadata: &ServiceMetadata, duration: u16) {
+    if metadata.packages.len() > 3 {
+        env::panic(b"Max 3 packages");
+    }
+    for package in metadata.packages.iter() {
+        if package.name.len() < 1 || package.name.len() > 20 {
+            env::panic(b"Package name between 1 and 20 characters");
+        }
+        if package.features.len() > 10 {
+            env::panic(b"Max 10 features per package");
+        }
+        if package.features.iter().any(|feature| feature.len() > 60) {
+            env::panic(b"Feature max 60 characters");
+        }
+        assert_valid_package_terms(package.price.0, package.duration, package.revisions, &package.milestones);
+    }
+    if metadata.subscription && (!metadata.milestones.is_empty()
+        || metadata.packages.iter().any(|package| !package.milestones.is_empty())) {
+        env::panic(b"Subscriptions are paid by period and can't define milestones");
+    }
+    if metadata.packages.is_empty() {
+        assert_valid_package_terms(metadata.price.0, duration, metadata.revisions, &metadata.milestones);
+    }
+}
+
+/// Verificar las revisiones incluidas y que las etapas sumen el precio
+/// y esten ordenadas dentro de la duracion.
+pub(crate) fn assert_valid_package_terms(price: u128, duration: u16, revisions: u8, milestones: &Vec<MilestoneTerms>) {
+    if revisions > 10 {
+        env::panic(b"Max 10 revisions");
+    }
+    if milestones.is_empty() {
+        return;
+    }
+    if milestones.len() > 10 {
+        env::panic(b"Max 10 milestones");
+    }
+
+    let mut total: u128 = 0;
+    let mut last_duration: u16 = 0;
+    for milestone in milestones.iter() {
+        if milestone.description.len() > 180 {
+            env::panic(b"Milestone description max 180 characters");
+        }
+        if milestone.duration < last_duration || milestone.duration > duration {
+            env::panic(b"Milestones must be ordered and within the service duration");
+        }
+        last_duration = milestone.duration;
+        total += milestone.amount.0;
+    }
+    if total != price {
+        env::panic(b"The milestones amounts must add up to the service price");
+    }
+}
+
+/// Si se definen paquetes, el listado toma el precio, las revisiones, las etapas y la duracion del primero.
+pub(crate) fn with_basic_package(mut metadata: ServiceMetadata, duration: u16) -> (ServiceMetadata, u16) {
+    let basic = match metadata.packages.first() {
+        Some(package) => package.clone(),
+        None => return (metadata, duration),
+    };
+    metadata.price = basic.price;
+    metadata.revisions = basic.revisions;
+    metadata.milestones = basic.milestones;
+    (metadata, basic.duration)
+}
+
 /// Internal function to Option values
 pub(crate) fn expect_value_found<T>(option: Option<T>, message: &[u8]) -> T {
     option.unwrap_or_else(|| env::panic(message))
@@ -75,6 +210,14 @@ impl Marketplace {
     } 
 
 
+    /// Verificar que no queden usuarios ni servicios con el formato anterior, que no pueden leerse
+    /// con el actual. Asi tampoco se eliminan registros que alteren el orden de los pendientes.
+    pub fn assert_migrated(&self) {
+        if self.legacy.is_some() {
+            env::panic(b"The contract is migrating legacy records, try again later");
+        }
+    }
+
     /// Verificar que el servicio exista.
     pub fn assert_service_exists(&self, service_id: &u64) {
         if *service_id > self.total_services {
@@ -82,11 +225,19 @@ impl Marketplace {
         }
     }
 
+    /// Verificar que la orden exista.
+    pub fn assert_order_exists(&self, order_id: &u64) {
+        if *order_id > self.total_orders {
+            env::panic(b"The indicated order doesn't exist")
+        }
+    }
+
     /*******************************/
     /******* GET FUNCTIONS  ********/
     /*******************************/
 
     pub fn get_users(&self, from_index: u64, limit: u64) -> Vec<(AccountId, User)> {
+        self.assert_migrated();
         let keys = self.users.keys_as_vector();
         let values = self.users.values_as_vector();
         (from_index..std::cmp::min(from_index + limit, self.users.len()))
@@ -98,6 +249,7 @@ impl Marketplace {
     /// #Arguments
     /// * `account_id`  - La cuenta de mainnet/testnet del usuario.
     pub fn get_user(&self, account_id: ValidAccountId) -> User {
+        self.assert_migrated();
         expect_value_found(self.users.get(&account_id.into()), b"No users found. Register the user first")
     }
 
@@ -130,10 +282,12 @@ impl Marketplace {
     /// #Arguments
     /// * `account_id`  - La cuenta de mainnet/testnet del usuario.
     pub fn get_user_service_id(&self, account_id: ValidAccountId) -> Vec<u64> {
+        self.assert_migrated();
         return expect_value_found(self.services_by_account.get(&account_id.into()), "No users found or dont have any service".as_bytes()).to_vec();
     }
 
     /// Obtener los servicios de determinado usuario.
+    /// Las ofertas privadas no se listan, su empleador las obtiene con `get_offers_for`.
     ///
     /// #Arguments
     /// * `account_id`  - La cuenta de mainnet/testnet del usuario.
@@ -143,6 +297,9 @@ impl Marketplace {
         let service_id = self.get_user_service_id(account_id.clone());
         for i in 0 .. service_id.len() {
             let service = expect_value_found(self.service_by_id.get(&service_id[i]), "Service id dont match".as_bytes());
+            if service.offer.is_some() {
+                continue;
+            }
             if only_on_sale {
                 if service.on_sale {
                     services.push( service ); 
@@ -156,6 +313,7 @@ impl Marketplace {
     /// #Arguments
     /// * `service_id`
     pub fn get_service_by_id(&self, service_id: u64) -> Service {
+        self.assert_migrated();
         return expect_value_found(self.service_by_id.get(&service_id.into()), "Service not found".as_bytes());
     }
 
@@ -163,6 +321,7 @@ impl Marketplace {
     /// #Arguments
     /// * `account_id`  - La cuenta de mainnet/testnet del usuario.
     pub fn get_service_by_ids(&self, ids: HashSet<u64>) -> Vec<Service> {
+        self.assert_migrated();
         if ids.len() > self.service_by_id.len() as usize {
             env::panic(b"The amounts of ids supere the amount of services");
         }
@@ -181,33 +340,49 @@ impl Marketplace {
         self.total_services
     }
 
+    /// Las ofertas privadas no se listan, se obtienen con `get_offers_for`.
     pub fn get_services(&self, from_index: u64, limit: u64) -> Vec<Service>{
+        self.assert_migrated();
         let values = self.service_by_id.values_as_vector();
         return (from_index..std::cmp::min(from_index + limit, self.service_by_id.len()))
             .map(|index| values.get(index).unwrap())
+            .filter(|service| service.offer.is_none())
             .collect();
     }
 
-    pub fn get_supported_tokens(&self) -> Vec<AccountId> {
-        self.tokens.to_vec()
+    /// #Arguments
+    /// * `order_id`
+    pub fn get_order_by_id(&self, order_id: u64) -> Order {
+        return expect_value_found(self.order_by_id.get(&order_id), "Order not found".as_bytes());
     }
 
-    pub fn get_ft_balance(&self, token: String) -> Balance {
-        let sender = env::predecessor_account_id();
-        if token == "usdc".to_string() {
-            return self.usdc_balances.get(&sender).unwrap_or(0);
-        }
-        else {
-            return self.jobs_balances.get(&sender).unwrap_or(0);
-        }
+    /// Obtener las ordenes de un usuario, tanto compradas como vendidas.
+    ///
+    /// #Arguments
+    /// * `account_id`  - La cuenta de mainnet/testnet del usuario.
+    pub fn get_user_orders(&self, account_id: ValidAccountId) -> Vec<Order> {
+        let orders_set = match self.orders_by_account.get(&account_id.into()) {
+            Some(set) => set,
+            None => return Vec::new(),
+        };
+        orders_set.iter()
+            .map(|id| expect_value_found(self.order_by_id.get(&id), "Order id dont match".as_bytes()))
+            .collect()
     }
 
-    pub fn get_ft_balance_of(&self, token: String, user: AccountId) -> Balance {
-        if token == "usdc".to_string() {
-            return self.usdc_balances.get(&user).unwrap_or(0);
-        }
-        else {
-            return self.jobs_balances.get(&user).unwrap_or(0);
-        }
+    /// Obtener el total de ordenes
+    pub fn get_total_orders(&self) -> u64 {
+        self.total_orders
+    }
+
+    pub fn get_orders(&self, from_index: u64, limit: u64) -> Vec<Order>{
+        let values = self.order_by_id.values_as_vector();
+        return (from_index..std::cmp::min(from_index + limit, self.order_by_id.len()))
+            .map(|index| values.get(index).unwrap())
+            .collect();
+    }
+
+    pub fn get_supported_tokens(&self) -> Vec<AccountId> {
+        self.tokens.to_vec()
     }
 }
@@ -0,0 +1,530 @@
+use crate::*;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum JobStatus {
+    Open,       // Recibiendo propuestas.
+    Assigned,   // Se acepto una propuesta y se genero su orden.
+    Closed,     // Cerrado por el empleador sin asignarse.
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum ProposalStatus {
+    Pending,
+    Accepted,
+    Withdrawn,  // Retirada por el profesional.
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct JobMetadata {
+    pub title: String,
+    pub description: String,
+    pub categories: String,
+    // Presupuesto orientativo en la unidad minima del token.
+    pub budget: U128,
+    pub token: AccountId,
+}
+
+/// Trabajo publicado por un empleador para recibir propuestas de profesionales.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Job {
+    pub id: u64,
+    pub metadata: JobMetadata,
+    pub employer_id: AccountId,
+    pub created_at: u64,
+    // Fecha limite para que el trabajo este terminado.
+    pub deadline: u64,
+    pub status: JobStatus,
+    // Orden generada al aceptarse una propuesta.
+    pub order_id: Option<u64>,
+}
+
+/// Propuesta de un profesional para realizar un trabajo.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Proposal {
+    pub id: u64,
+    pub job_id: u64,
+    pub professional_id: AccountId,
+    // Precio en la unidad minima del token del trabajo.
+    pub price: U128,
+    // Dias que va a durar el trabajo desde que se acepta la propuesta.
+    pub duration: u16,
+    pub description: String,
+    pub created_at: u64,
+    pub status: ProposalStatus,
+}
+
+#[near_bindgen]
+impl Marketplace {
+    /// Publicar un trabajo para recibir propuestas.
+    /// Solo ejecutable por empleadores.
+    ///
+    /// #Arguments
+    /// * `metadata`    - Titulo, descripcion, categorias, presupuesto y token del trabajo.
+    /// * `deadline`    - Dias desde la publicacion para que el trabajo este terminado.
+    #[payable]
+    pub fn post_job(&mut self, metadata: JobMetadata, deadline: u16) -> Job {
+        let sender = env::predecessor_account_id();
+
+        let user = self.get_user(string_to_valid_account_id(&sender).clone());
+        if user.is_employee {
+            env::panic(b"Only employers can post a job");
+        }
+
+        if metadata.title.len() > 58 {
+            env::panic(b"Title max 58 characters");
+        }
+        else if metadata.title.len() < 10 {
+            env::panic(b"Title min 10 characters");
+        }
+        if metadata.description.len() > 400 {
+            env::panic(b"Description max 400 characters");
+        }
+        else if metadata.description.len() < 10 {
+            env::panic(b"Description min 10 characters");
+        }
+        let categories: Vec<String> = serde_json::from_str(&metadata.categories).unwrap();
+        if categories.len() > 15 {
+            env::panic(b"Max 15 categories");
+        }
+        else if categories.len() < 1 {
+            env::panic(b"Min 1 categories");
+        }
+        if !self.tokens.contains(&metadata.token) {
+            env::panic(b"Token not soported");
+        }
+        if deadline == 0 {
+            env::panic(b"The deadline must be at least one day");
+        }
+
+        let initial_storage_usage = env::storage_usage();
+
+        self.total_jobs += 1;
+        let now = env::block_timestamp();
+        let job = Job {
+            id: self.total_jobs.clone(),
+            metadata: metadata,
+            employer_id: sender.clone(),
+            created_at: now,
+            deadline: now + ONE_DAY * (deadline as u64),
+            status: JobStatus::Open,
+            order_id: None,
+        };
+        if self.job_by_id.insert(&job.id, &job).is_some() {
+            env::panic(b"Job already exists");
+        }
+
+        let mut jobs_set = self
+            .jobs_by_account
+            .get(&sender)
+            .unwrap_or_else(|| UnorderedSet::new(unique_jobs_prefix(&sender)));
+        jobs_set.insert(&job.id);
+        self.jobs_by_account.insert(&sender, &jobs_set);
+
+        NearEvent::log_job_new(
+            job.id.clone().to_string(),
+            sender,
+            job.metadata.title.clone(),
+            job.metadata.budget.0.to_string(),
+            job.metadata.token.clone(),
+            job.deadline.to_string(),
+        );
+
+        deposit_refund(env::storage_usage() - initial_storage_usage);
+        job
+    }
+
+
+    /// Cerrar un trabajo que aun no fue asignado.
+    /// Solo ejecutable por el empleador que lo publico.
+    ///
+    /// #Arguments
+    /// * `job_id`
+    pub fn close_job(&mut self, job_id: u64) -> Job {
+        let mut job = self.get_job_by_id(job_id.clone());
+
+        if job.employer_id != env::predecessor_account_id() {
+            env::panic(b"Only the employer that posted the job can close it");
+        }
+        if job.status != JobStatus::Open {
+            env::panic(b"The job isn't open");
+        }
+
+        job.status = JobStatus::Closed;
+        self.job_by_id.insert(&job_id, &job);
+
+        NearEvent::log_job_update_status(job_id.to_string(), "Closed".to_string());
+        job
+    }
+
+
+    /// Enviar una propuesta para un trabajo abierto.
+    /// Solo ejecutable por profesionales, una propuesta activa por trabajo.
+    ///
+    /// #Arguments
+    /// * `job_id`
+    /// * `price`       - Precio propuesto en la unidad minima del token del trabajo.
+    /// * `duration`    - Dias que va a llevar el trabajo una vez aceptada la propuesta.
+    /// * `description` - Detalle de la propuesta.
+    #[payable]
+    pub fn submit_proposal(&mut self, job_id: u64, price: U128, duration: u16, description: String) -> Proposal {
+        let job = self.get_job_by_id(job_id.clone());
+        let sender = env::predecessor_account_id();
+
+        let user = self.get_user(string_to_valid_account_id(&sender).clone());
+        if !user.is_employee {
+            env::panic(b"Only professionals can submit a proposal");
+        }
+        if job.status != JobStatus::Open {
+            env::panic(b"The job isn't open");
+        }
+        if price.0 == 0 {
+            env::panic(b"The price must be greater than zero");
+        }
+        if duration == 0 {
+            env::panic(b"The duration must be at least one day");
+        }
+        if env::block_timestamp() + ONE_DAY * (duration as u64) > job.deadline {
+            env::panic(b"The duration exceeds the job deadline");
+        }
+        if description.len() > 400 {
+            env::panic(b"Description max 400 characters");
+        }
+        if self.get_job_proposals(job_id.clone()).iter()
+            .any(|p| p.professional_id == sender && p.status == ProposalStatus::Pending) {
+            env::panic(b"You already have a proposal for this job");
+        }
+
+        let initial_storage_usage = env::storage_usage();
+
+        self.total_proposals += 1;
+        let proposal = Proposal {
+            id: self.total_proposals.clone(),
+            job_id: job_id.clone(),
+            professional_id: sender.clone(),
+            price: price,
+            duration: duration,
+            description: description,
+            created_at: env::block_timestamp(),
+            status: ProposalStatus::Pending,
+        };
+        self.proposal_by_id.insert(&proposal.id, &proposal);
+
+        let mut proposals_set = self
+            .proposals_by_job
+            .get(&job_id)
+            .unwrap_or_else(|| UnorderedSet::new(unique_job_prefix(&job_id)));
+        proposals_set.insert(&proposal.id);
+        self.proposals_by_job.insert(&job_id, &proposals_set);
+
+        NearEvent::log_job_proposal(
+            job_id.to_string(),
+            proposal.id.clone().to_string(),
+            sender,
+            price.0.to_string(),
+            duration.to_string(),
+        );
+
+        deposit_refund(env::storage_usage() - initial_storage_usage);
+        proposal
+    }
+
+
+    /// Retirar una propuesta que aun no fue aceptada.
+    ///
+    /// #Arguments
+    /// * `proposal_id`
+    pub fn withdraw_proposal(&mut self, proposal_id: u64) -> Proposal {
+        let mut proposal = self.get_proposal_by_id(proposal_id.clone());
+
+        if proposal.professional_id != env::predecessor_account_id() {
+            env::panic(b"Only the professional that submitted the proposal can withdraw it");
+        }
+        if proposal.status != ProposalStatus::Pending {
+            env::panic(b"The proposal isn't pending");
+        }
+
+        proposal.status = ProposalStatus::Withdrawn;
+        self.proposal_by_id.insert(&proposal_id, &proposal);
+        proposal
+    }
+
+
+    /// Aceptar una propuesta, generando una orden con el pago bloqueado en escrow.
+    /// La orden comienza en curso, ya que el profesional acepto los terminos al proponerlos,
+    /// y sigue los mismos caminos de aprobacion, reclamo y disputa que la compra de un servicio.
+    /// No se puede aceptar si la propuesta ya no llega a terminarse antes de la fecha limite del trabajo.
+    ///
+    /// #Arguments
+    /// * `proposal_id`
+    #[payable]
+    pub fn accept_proposal(&mut self, proposal_id: u64) -> Order {
+        let mut proposal = self.get_proposal_by_id(proposal_id.clone());
+        let mut job = self.get_job_by_id(proposal.job_id.clone());
+        let sender = env::predecessor_account_id();
+
+        if job.employer_id != sender {
+            env::panic(b"Only the employer that posted the job can accept a proposal");
+        }
+        if job.status != JobStatus::Open {
+            env::panic(b"The job isn't open");
+        }
+        if proposal.status != ProposalStatus::Pending {
+            env::panic(b"The proposal isn't pending");
+        }
+        // El trabajo tiene que poder terminarse antes de la fecha limite publicada.
+        let now = env::block_timestamp();
+        if now >= job.deadline {
+            env::panic(b"The job deadline already passed");
+        }
+        if now + ONE_DAY * (proposal.duration as u64) > job.deadline {
+            env::panic(b"The duration exceeds the job deadline");
+        }
+
+        // Cobrar desde la wallet del Marketplace, el pago queda bloqueado en escrow.
+        self.internal_deposit_attached(&sender);
+        self.internal_withdraw(&job.metadata.token, &sender, proposal.price.0);
+
+        let order = self.internal_new_job_order(&job, &proposal);
+        self.internal_lock_escrow(&order);
+
+        proposal.status = ProposalStatus::Accepted;
+        self.proposal_by_id.insert(&proposal_id, &proposal);
+
+        job.status = JobStatus::Assigned;
+        job.order_id = Some(order.id.clone());
+        self.job_by_id.insert(&job.id, &job);
+
+        NearEvent::log_job_update_status(job.id.to_string(), "Assigned".to_string());
+        NearEvent::log_order_update_status(order.id.to_string(), order.status.to_string());
+        order
+    }
+
+
+    /// Crear la orden de una propuesta aceptada, con una unica etapa por el precio propuesto.
+    pub(crate) fn internal_new_job_order(&mut self, job: &Job, proposal: &Proposal) -> Order {
+        let now = env::block_timestamp();
+        let deadline = now + ONE_DAY * (proposal.duration as u64);
+
+        self.internal_insert_order(Order {
+            id: 0,
+            service_id: None,
+            package_id: 0,
+            job_id: Some(job.id.clone()),
+            buyer_id: job.employer_id.clone(),
+            creator_id: proposal.professional_id.clone(),
+            price: proposal.price,
+            token: job.metadata.token.clone(),
+            duration: proposal.duration.clone(),
+            created_at: now,
+            buy_moment: now,
+            deadline: deadline,
+            finish_moment: None,
+            status: OrderStatus::InProgress,
+            milestones: vec![Milestone {
+                description: job.metadata.title.clone(),
+                amount: proposal.price,
+                duration: proposal.duration.clone(),
+                deadline: deadline,
+                status: MilestoneStatus::Pending,
+            }],
+            delivery: None,
+            revisions: 0,
+            revision_requests: Vec::new(),
+            cancellation: None,
+            deadline_proposal: None,
+            subscription: false,
+            hourly: None,
+            referrer: None,
+            tip: None,
+        })
+    }
+
+
+    /// #Arguments
+    /// * `job_id`
+    pub fn get_job_by_id(&self, job_id: u64) -> Job {
+        expect_value_found(self.job_by_id.get(&job_id), b"Job not found")
+    }
+
+    /// #Arguments
+    /// * `proposal_id`
+    pub fn get_proposal_by_id(&self, proposal_id: u64) -> Proposal {
+        expect_value_found(self.proposal_by_id.get(&proposal_id), b"Proposal not found")
+    }
+
+    /// Obtener las propuestas recibidas por un trabajo.
+    ///
+    /// #Arguments
+    /// * `job_id`
+    pub fn get_job_proposals(&self, job_id: u64) -> Vec<Proposal> {
+        match self.proposals_by_job.get(&job_id) {
+            Some(set) => set.iter()
+                .map(|id| expect_value_found(self.proposal_by_id.get(&id), b"Proposal id dont match"))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Obtener los trabajos publicados por un empleador.
+    ///
+    /// #Arguments
+    /// * `account_id`  - La cuenta de mainnet/testnet del empleador.
+    pub fn get_user_jobs(&self, account_id: ValidAccountId) -> Vec<Job> {
+        match self.jobs_by_account.get(&account_id.into()) {
+            Some(set) => set.iter()
+                .map(|id| expect_value_found(self.job_by_id.get(&id), b"Job id dont match"))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Obtener el total de trabajos publicados.
+    pub fn get_total_jobs(&self) -> u64 {
+        self.total_jobs
+    }
+
+    /// #Arguments
+    /// * `only_open`   - Retornar solo los trabajos que reciben propuestas.
+    pub fn get_jobs(&self, from_index: u64, limit: u64, only_open: bool) -> Vec<Job> {
+        let values = self.job_by_id.values_as_vector();
+        (from_index..std::cmp::min(from_index + limit, self.job_by_id.len()))
+            .map(|index| values.get(index).unwrap())
+            .filter(|job| !only_open || job.status == JobStatus::Open)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use crate::test_utils::*;
+
+    fn job_metadata() -> JobMetadata {
+        JobMetadata {
+            title: "Marketplace landing page".to_string(),
+            description: "Design and build the landing page".to_string(),
+            categories: "[\"design\"]".to_string(),
+            budget: U128(PRICE),
+            token: "near".to_string(),
+        }
+    }
+
+    fn post(context: &mut VMContextBuilder, contract: &mut Marketplace) -> u64 {
+        set_caller(context, 2, STORAGE_DEPOSIT);
+        contract.post_job(job_metadata(), 10).id
+    }
+
+    fn propose(context: &mut VMContextBuilder, contract: &mut Marketplace, job_id: u64) -> u64 {
+        set_caller(context, 1, STORAGE_DEPOSIT);
+        contract.submit_proposal(job_id, U128(8_000), 5, "Two revisions included".to_string()).id
+    }
+
+    #[test]
+    fn accepted_proposals_become_escrowed_orders() {
+        let (mut context, mut contract) = setup();
+        let job_id = post(&mut context, &mut contract);
+        let proposal_id = propose(&mut context, &mut contract, job_id);
+
+        set_caller(&mut context, 2, 8_000);
+        let order = contract.accept_proposal(proposal_id);
+
+        assert_eq!(order.status, OrderStatus::InProgress);
+        assert_eq!(order.creator_id, id(1));
+        assert_eq!(order.price.0, 8_000);
+        assert_eq!(contract.get_escrow(order.id).amount.0, 8_000);
+        let job = contract.get_job_by_id(job_id);
+        assert_eq!(job.status, JobStatus::Assigned);
+        assert_eq!(job.order_id, Some(order.id));
+        assert_eq!(contract.get_proposal_by_id(proposal_id).status, ProposalStatus::Accepted);
+    }
+
+    #[test]
+    #[should_panic(expected = "Insufficient near balance in the marketplace wallet")]
+    fn accepting_requires_the_proposal_price() {
+        let (mut context, mut contract) = setup();
+        let job_id = post(&mut context, &mut contract);
+        let proposal_id = propose(&mut context, &mut contract, job_id);
+
+        set_caller(&mut context, 2, 7_999);
+        contract.accept_proposal(proposal_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the employer that posted the job can accept a proposal")]
+    fn only_the_job_employer_accepts() {
+        let (mut context, mut contract) = setup();
+        let job_id = post(&mut context, &mut contract);
+        let proposal_id = propose(&mut context, &mut contract, job_id);
+
+        set_caller(&mut context, 3, 8_000);
+        contract.accept_proposal(proposal_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only employers can post a job")]
+    fn only_employers_post_jobs() {
+        let (mut context, mut contract) = setup();
+
+        set_caller(&mut context, 1, STORAGE_DEPOSIT);
+        contract.post_job(job_metadata(), 10);
+    }
+
+    #[test]
+    #[should_panic(expected = "The job isn't open")]
+    fn closed_jobs_dont_take_proposals() {
+        let (mut context, mut contract) = setup();
+        let job_id = post(&mut context, &mut contract);
+        contract.close_job(job_id);
+
+        propose(&mut context, &mut contract, job_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "You already have a proposal for this job")]
+    fn one_pending_proposal_per_professional() {
+        let (mut context, mut contract) = setup();
+        let job_id = post(&mut context, &mut contract);
+
+        propose(&mut context, &mut contract, job_id);
+        propose(&mut context, &mut contract, job_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "The duration exceeds the job deadline")]
+    fn proposals_fit_the_job_deadline() {
+        let (mut context, mut contract) = setup();
+        let job_id = post(&mut context, &mut contract);
+
+        set_caller(&mut context, 1, STORAGE_DEPOSIT);
+        contract.submit_proposal(job_id, U128(8_000), 11, "Two revisions included".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "The duration exceeds the job deadline")]
+    fn late_acceptances_must_still_fit_the_job_deadline() {
+        let (mut context, mut contract) = setup();
+        let job_id = post(&mut context, &mut contract);
+        let proposal_id = propose(&mut context, &mut contract, job_id);
+
+        at_day(&mut context, 6);
+        set_caller(&mut context, 2, 8_000);
+        contract.accept_proposal(proposal_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "The job deadline already passed")]
+    fn proposals_cant_be_accepted_after_the_job_deadline() {
+        let (mut context, mut contract) = setup();
+        let job_id = post(&mut context, &mut contract);
+        let proposal_id = propose(&mut context, &mut contract, job_id);
+
+        at_day(&mut context, 10);
+        set_caller(&mut context, 2, 8_000);
+        contract.accept_proposal(proposal_id);
+    }
+}
@@ -9,10 +9,22 @@ use std::collections::{HashSet};
 use std::convert::TryFrom;
 
 use crate::user::*;
+use crate::order::*;
+use crate::cancel::*;
+use crate::job::*;
+use crate::offer::*;
+use crate::referral::*;
+use crate::review::*;
+use crate::reputation::*;
+use crate::migration::*;
+use crate::escrow::*;
+use crate::tokens::*;
 use crate::internal::*;
 use crate::external::*;
 pub use event::*;
-mod internal; mod user; mod external; mod event;
+mod internal; mod user; mod order; mod cancel; mod deadline; mod job; mod offer; mod subscription; mod hourly; mod cart; mod treasury; mod referral; mod review; mod reputation; mod migration; mod escrow; mod tokens; mod wallet; mod external; mod event;
+#[cfg(test)]
+mod test_utils;
 
 near_sdk::setup_alloc!();
 
@@ -21,7 +33,6 @@ const BASE_GAS: Gas = 30_000_000_000_000;
 const GAS_FT_TRANSFER: Gas = 10_000_000_000_000;
 const ONE_DAY: u64 = 86400000000000;
 const ONE_YOCTO: Balance = 1;
-const DECIMALS: Balance = 1_000_000;
 
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
 #[serde(crate = "near_sdk::serde")]
@@ -29,18 +40,12 @@ pub struct Service {
     pub id: u64,
     pub metadata: ServiceMetadata,
     pub creator_id: AccountId,
-    pub actual_owner: AccountId,
-    pub employers_account_ids: HashSet<AccountId>,
     // Dias que va a durar el trabajo ofrecido.
     pub duration: u16,
-    // Uso de timestamp para fijar momento de compra.
-    pub buy_moment: u64,
-    // Determinar si esta en manos del profesional (false) o de un empleador (true).
-    pub sold: bool,
     // Determinar si esta en venta.
     pub on_sale: bool,
-    // Determinar si esta en disputa.
-    pub on_dispute: bool,
+    // Solo para ofertas privadas a un empleador.
+    pub offer: Option<PrivateOffer>,
 }
 
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
@@ -50,8 +55,56 @@ pub struct ServiceMetadata {
     pub description: String,
     pub categories: String,
     pub icon: String,
-    pub price: u128,
-    pub token: AccountId
+    // Precio en la unidad minima del token (yoctoNEAR o 10^decimals del FT).
+    pub price: U128,
+    pub token: AccountId,
+    // Etapas opcionales del servicio. La suma de sus montos debe ser igual al precio.
+    #[serde(default)]
+    pub milestones: Vec<MilestoneTerms>,
+    // Cantidad de revisiones incluidas en la compra.
+    #[serde(default)]
+    pub revisions: u8,
+    // Paquetes opcionales (basico, estandar, premium). Si se definen, el precio, las revisiones,
+    // las etapas y la duracion del listado son los del primero.
+    #[serde(default)]
+    pub packages: Vec<Package>,
+    // Servicio recurrente: el precio es por periodo y la duracion es la de cada periodo.
+    #[serde(default)]
+    pub subscription: bool,
+}
+
+/// Nivel de un servicio con su propio precio, duracion, revisiones y caracteristicas.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Package {
+    pub name: String,
+    // Precio en la unidad minima del token del servicio.
+    pub price: U128,
+    pub duration: u16,
+    pub revisions: u8,
+    pub features: Vec<String>,
+    #[serde(default)]
+    pub milestones: Vec<MilestoneTerms>,
+}
+
+impl Service {
+    /// Obtener un paquete del servicio. Sin paquetes definidos, el unico es el listado en si.
+    pub fn package(&self, package_id: u8) -> Package {
+        if self.metadata.packages.is_empty() {
+            if package_id != 0 {
+                env::panic(b"The indicated package doesn't exist");
+            }
+            return Package {
+                name: "Basic".to_string(),
+                price: self.metadata.price,
+                duration: self.duration.clone(),
+                revisions: self.metadata.revisions.clone(),
+                features: Vec::new(),
+                milestones: self.metadata.milestones.clone(),
+            };
+        }
+        expect_value_found(self.metadata.packages.get(package_id as usize).cloned(), b"The indicated package doesn't exist")
+    }
 }
 
 #[near_bindgen]
@@ -61,20 +114,72 @@ pub struct Marketplace {
     // Servicios de cada usuario.
     pub services_by_account: LookupMap<AccountId, UnorderedSet<u64>>,
     pub total_services: u64,
+    // Compras de servicios, independientes de su listado.
+    pub order_by_id: UnorderedMap<u64, Order>,
+    // Ordenes de cada usuario, tanto como comprador o como profesional.
+    pub orders_by_account: LookupMap<AccountId, UnorderedSet<u64>>,
+    pub total_orders: u64,
+    // Fondos bloqueados de cada orden y total bloqueado por token.
+    pub escrows: LookupMap<u64, Escrow>,
+    pub escrow_totals: UnorderedMap<AccountId, Balance>,
     // Usuarios del marketplace.
     pub users: UnorderedMap<AccountId, User>,
     pub owner: AccountId,
     pub admins: UnorderedSet<AccountId>,
     pub contract_me: AccountId,
     pub contract_ft: AccountId,
-    // Tokens soportados.
+    // Tokens soportados y su symbol y decimales.
     pub tokens: UnorderedSet<AccountId>,
-    pub usdc_contract: AccountId,
-    pub jobs_contract: AccountId,
-    // Balance disponible de tokens de los usuarios.
-    pub usdc_balances: LookupMap<AccountId, Balance>,
-    pub jobs_balances: LookupMap<AccountId, Balance>,
-    pub average_block_time: u64
+    pub tokens_info: LookupMap<AccountId, TokenInfo>,
+    // Balance disponible de tokens de los usuarios, por (token, usuario).
+    pub balances: LookupMap<(AccountId, AccountId), Balance>,
+    // Total depositado de cada token en las wallets de los usuarios.
+    pub balance_totals: LookupMap<AccountId, Balance>,
+    pub average_block_time: u64,
+    // Dias que tiene el empleador para revisar una entrega antes de que pueda cobrarse.
+    pub review_period: u16,
+    // Dias que se extiende la fecha limite de una orden con cada revision.
+    pub revision_extension: u16,
+    // Horas desde la aceptacion en las que el empleador puede cancelar con reembolso total.
+    pub cancel_grace_period: u16,
+    // Horas que tiene el profesional para aceptar una orden antes de que se rechace.
+    pub acceptance_period: u16,
+    // Trabajos publicados por empleadores y propuestas de los profesionales.
+    pub job_by_id: UnorderedMap<u64, Job>,
+    pub jobs_by_account: LookupMap<AccountId, UnorderedSet<u64>>,
+    pub total_jobs: u64,
+    pub proposal_by_id: UnorderedMap<u64, Proposal>,
+    pub proposals_by_job: LookupMap<u64, UnorderedSet<u64>>,
+    pub total_proposals: u64,
+    // Ofertas privadas recibidas por cada empleador.
+    pub offers_by_buyer: LookupMap<AccountId, UnorderedSet<u64>>,
+    // Comision de la plataforma en puntos basicos, por defecto y por token.
+    pub default_fee: u16,
+    pub fee_by_token: LookupMap<AccountId, u16>,
+    // Comisiones disponibles en la tesoreria, total cobrado y cobrado por (token, periodo).
+    pub treasury: UnorderedMap<AccountId, Balance>,
+    pub fee_totals: LookupMap<AccountId, Balance>,
+    pub fees_by_period: LookupMap<(AccountId, u64), Balance>,
+    // Quien refirio a cada usuario al registrarse.
+    pub referrers: LookupMap<AccountId, AccountId>,
+    // Parte de la comision de la plataforma que recibe el referidor, en puntos basicos.
+    pub referral_commission: u16,
+    // Ganancias por referidos de cada cuenta, por (cuenta, token).
+    pub referral_earnings: LookupMap<(AccountId, AccountId), ReferralEarnings>,
+    // Total de comisiones por referidos aun sin cobrar de cada token, en poder del contrato.
+    pub referral_totals: LookupMap<AccountId, Balance>,
+    // Resenias de ordenes completadas, por orden, por servicio y por usuario que las recibe.
+    pub review_by_id: LookupMap<u64, Review>,
+    pub review_by_order: LookupMap<u64, u64>,
+    pub employer_review_by_order: LookupMap<u64, u64>,
+    pub reviews_by_service: LookupMap<u64, UnorderedSet<u64>>,
+    pub reviews_by_account: LookupMap<AccountId, UnorderedSet<u64>>,
+    pub total_reviews: u64,
+    // Parametros y puntajes acumulados de la reputacion, por (usuario, rol).
+    pub reputation_params: ReputationParams,
+    pub reputation_by_account: LookupMap<(AccountId, ReputationRole), ReputationScore>,
+    // Usuarios y servicios pendientes de convertir desde el formato anterior.
+    pub legacy: Option<LegacyMigration>,
 }
 
 #[near_bindgen]
@@ -105,19 +210,59 @@ impl Marketplace {
             contract_me: mediator.clone().into(),
             contract_ft: ft.clone().into(),
             tokens: UnorderedSet::new(b"d".to_vec()),
-            usdc_contract: usdc.into(),
-            jobs_contract: ft.into(),
-            usdc_balances: LookupMap::new(b"e".to_vec()),
-            jobs_balances: LookupMap::new(b"f".to_vec()),
-            average_block_time: 121
+            balances: LookupMap::new(b"e".to_vec()),
+            balance_totals: LookupMap::new(b"f".to_vec()),
+            average_block_time: 121,
+            order_by_id: UnorderedMap::new(b"g".to_vec()),
+            orders_by_account: LookupMap::new(b"h".to_vec()),
+            total_orders: 0,
+            escrows: LookupMap::new(b"i".to_vec()),
+            escrow_totals: UnorderedMap::new(b"j".to_vec()),
+            tokens_info: LookupMap::new(b"k".to_vec()),
+            review_period: 3,
+            revision_extension: 3,
+            cancel_grace_period: 24,
+            acceptance_period: 48,
+            job_by_id: UnorderedMap::new(b"l".to_vec()),
+            jobs_by_account: LookupMap::new(b"m".to_vec()),
+            total_jobs: 0,
+            proposal_by_id: UnorderedMap::new(b"n".to_vec()),
+            proposals_by_job: LookupMap::new(b"p".to_vec()),
+            total_proposals: 0,
+            offers_by_buyer: LookupMap::new(b"s".to_vec()),
+            default_fee: 0,
+            fee_by_token: LookupMap::new(b"v".to_vec()),
+            treasury: UnorderedMap::new(b"w".to_vec()),
+            fee_totals: LookupMap::new(b"x".to_vec()),
+            fees_by_period: LookupMap::new(b"y".to_vec()),
+            referrers: LookupMap::new(b"z".to_vec()),
+            referral_commission: 1000,
+            referral_earnings: LookupMap::new(b"A".to_vec()),
+            referral_totals: LookupMap::new(b"J".to_vec()),
+            review_by_id: LookupMap::new(b"B".to_vec()),
+            review_by_order: LookupMap::new(b"C".to_vec()),
+            employer_review_by_order: LookupMap::new(b"H".to_vec()),
+            reviews_by_service: LookupMap::new(b"D".to_vec()),
+            reviews_by_account: LookupMap::new(b"E".to_vec()),
+            total_reviews: 0,
+            reputation_params: ReputationParams::new(),
+            reputation_by_account: LookupMap::new(b"I".to_vec()),
+            legacy: None,
         };
         // Agregar NEAR por default.
         this.tokens.insert(&"near".to_string());
-
-        // Agregar otros tokens en caso de haberse indicado como parametro.
+        this.tokens_info.insert(&"near".to_string(), &TokenInfo {
+            symbol: "NEAR".to_string(),
+            decimals: NEAR_DECIMALS,
+        });
+
+        // Registrar JOBS, USDC y otros tokens en caso de haberse indicado como parametro,
+        // una vez obtenida su metadata.
+        this.internal_register_token(ft.as_ref());
+        this.internal_register_token(usdc.as_ref());
         if let Some(tokens) = tokens {
             for id in tokens {
-                this.tokens.insert(id.as_ref());
+                this.internal_register_token(id.as_ref());
             }
         }
         this
@@ -128,89 +273,61 @@ impl Marketplace {
     /****** SERVICES FUNCTIONS *****/
     /*******************************/
 
-    /// Mintea uno o varios servicios.
+    /// Mintea un servicio.
     /// Solo ejecutable por profesionales. 
+    /// El mismo servicio puede ser comprado por varios empleadores a la vez, cada compra genera una orden.
     ///
     /// #Arguments
     /// * `metadata`    - La metadata que el profesional asigna a su servicio.
-    /// * `quantity`    - La cantidad de services que se desea mintear.
     /// * `duration`    - Duracion en dias estimada para realizarse el servicio.
     #[payable]
-    pub fn mint_service(&mut self, metadata: ServiceMetadata, quantity: u16, duration: u16) -> Service {
+    pub fn mint_service(&mut self, metadata: ServiceMetadata, duration: u16) -> Service {
         let sender = env::predecessor_account_id();
 
-        if metadata.title.len() > 58 {
-            env::panic(b"Title max 58 characters");
-        }
-        else if metadata.title.len() < 10 {
-            env::panic(b"Title min 10 characters");
-        }
+        assert_valid_service_metadata(&metadata);
 
-        if metadata.description.len() > 180 {
-            env::panic(b"Description max 180 characters");
-        }
-        else if metadata.description.len() > 180 {
-            env::panic(b"Description min 10 characters");
-        }
-
-        let categories: Vec<String> = serde_json::from_str(&metadata.categories).unwrap();
-        if categories.len() > 15 {
-            env::panic(b"Max 15 categories");
-        }
-        else if categories.len() < 1 {
-            env::panic(b"Min 1 categories");
+        if !self.tokens.contains(&metadata.token) {
+            env::panic(b"Token not soported");
         }
+        assert_valid_service_terms(&metadata, duration);
+        let (metadata, duration) = with_basic_package(metadata, duration);
 
         let initial_storage_usage = env::storage_usage();
 
         //Verificar que sea un profesional
         let user = self.get_user(string_to_valid_account_id(&sender).clone()); // Cantidad de servicios
-        assert_eq!(user.is_employee == true, b"Only professionals can mint a service");
+        if !user.is_employee {
+            env::panic(b"Only professionals can mint a service");
+        }
         
         // env::log(format!("initial store usage: {}", initial_storage_usage).as_bytes());
 
-        let mut service = Service {
+        self.total_services += 1;
+
+        let service = Service {
             id: self.total_services.clone(),
             creator_id: sender.clone(),
             metadata: metadata,
-            employers_account_ids: Default::default(),
-            actual_owner: sender.clone(),
             duration: duration,
-            buy_moment: 0,
-            sold: false,
             on_sale: true,
-            on_dispute: false,
+            offer: None,
         };
         
-        let mut services_set = self
-            .services_by_account
-            .get(&sender)
-            .unwrap_or_else(|| UnorderedSet::new(unique_prefix(&sender)));
-
-        for _i in 0 .. quantity {
-            self.total_services += 1;
-
-            service.id = self.total_services.clone();
-            service.on_sale = true;
-            
-            if self.service_by_id.insert(&self.total_services.clone(), &service).is_some() {
-                env::panic(b"Service already exists");
-            }
-            
-            services_set.insert(&self.total_services.clone());
-
-            NearEvent::log_service_mint(
-                service.id.clone().to_string(),
-                service.actual_owner.clone().to_string(),
-                service.metadata.title.clone(),
-                service.metadata.description.clone(),
-                service.metadata.categories.clone(),
-                service.metadata.price.clone().to_string(),
-                service.duration.clone().to_string(),
-            );
+        if self.service_by_id.insert(&service.id, &service).is_some() {
+            env::panic(b"Service already exists");
         }
+        self.add_service(&service.id, &sender);
 
-        self.services_by_account.insert(&sender, &services_set);
+        NearEvent::log_service_mint(
+            service.id.clone().to_string(),
+            service.creator_id.clone().to_string(),
+            service.metadata.title.clone(),
+            service.metadata.description.clone(),
+            service.metadata.categories.clone(),
+            service.metadata.price.0.to_string(),
+            service.duration.clone().to_string(),
+            serde_json::to_string(&service.metadata.packages).unwrap(),
+        );
 
         // Manejo del storage.
         let required_storage_in_bytes = env::storage_usage() - initial_storage_usage;
@@ -222,148 +339,253 @@ impl Marketplace {
 
 
     /// Adquisicion de un servicio.
-    /// Solo ejecutable por empleadores.
+    /// Solo ejecutable por empleadores. Genera una nueva orden sin modificar el servicio.
+    /// Se paga con la wallet del Marketplace, a la que se suma el NEAR adjunto.
+    ///
+    /// #Arguments
+    /// * `service_id`
+    /// * `package_id`  - Indice del paquete a comprar, 0 si el servicio no define paquetes.
+    /// * `referrer`    - Cuenta que refirio la compra, por defecto quien refirio al empleador.
     #[payable]
-    pub fn buy_service(&mut self, service_id: u64) {
-        // Verificar que el servicio exista.
-        self.assert_service_exists(&service_id);
+    pub fn buy_service(&mut self, service_id: u64, package_id: u8, referrer: Option<ValidAccountId>) {
+        let sender = env::predecessor_account_id();
+        let buyer = self.get_user(string_to_valid_account_id(&sender).clone());
 
-        let mut service = self.get_service_by_id(service_id.clone());
-        
-        // Verificar que este en venta.
-        if !service.on_sale {
-            env::panic(b"The indicated service is not on sale")
+        let (service, package) = self.internal_check_purchase(service_id, package_id, &buyer.account_id);
+
+        // Cobrar desde la wallet del Marketplace, el pago queda bloqueado en escrow.
+        // El NEAR adjunto se acredita antes, por lo que lo que sobre queda en la wallet.
+        self.internal_deposit_attached(&buyer.account_id);
+        self.internal_withdraw(&service.metadata.token, &buyer.account_id, package.price.0);
+
+        let referrer = referrer.map(|id| self.internal_check_referrer(id.into(), &buyer.account_id));
+        self.internal_place_order(&service, package_id, &package, &buyer.account_id, referrer);
+    }
+
+
+    /// Aceptar una orden, iniciando el tiempo de trabajo desde este momento.
+    /// Solo ejecutable por el profesional antes de que venza el plazo para responder.
+    ///
+    /// #Arguments
+    /// * `order_id`
+    pub fn accept_order(&mut self, order_id: u64) -> Order {
+        self.assert_order_exists(&order_id);
+
+        let mut order = self.get_order_by_id(order_id.clone());
+
+        if order.creator_id != env::predecessor_account_id() {
+            env::panic(b"Only the corresponding professional can accept the order");
+        }
+        if order.status != OrderStatus::Pending {
+            env::panic(b"The order isn't pending of acceptance");
+        }
+        let now = env::block_timestamp();
+        if now > self.internal_acceptance_end(&order) {
+            env::panic(b"The time to accept the order is over");
         }
 
-        let sender = env::predecessor_account_id();
-        let buyer = self.get_user(string_to_valid_account_id(&sender).clone());
+        order.status = OrderStatus::InProgress;
+        order.buy_moment = now;
+        order.deadline = now + ONE_DAY * (order.duration as u64);
+        for milestone in order.milestones.iter_mut() {
+            milestone.deadline = now + ONE_DAY * (milestone.duration as u64);
+        }
+        self.order_by_id.insert(&order_id, &order);
+
+        NearEvent::log_order_update_status(
+            order_id.clone().to_string(),
+            order.status.to_string()
+        );
+        order
+    }
+
 
-        // Verificar que no lo haya comprado ya.
-        if buyer.account_id == service.actual_owner.clone() {
-            env::panic(b"Already is the service owner");
+    /// Rechazar una orden, devolviendo el pago al empleador.
+    /// Solo ejecutable por el profesional mientras la orden no fue aceptada.
+    ///
+    /// #Arguments
+    /// * `order_id`
+    pub fn reject_order(&mut self, order_id: u64) -> Order {
+        self.assert_order_exists(&order_id);
+
+        let order = self.get_order_by_id(order_id.clone());
+
+        if order.creator_id != env::predecessor_account_id() {
+            env::panic(b"Only the corresponding professional can reject the order");
+        }
+        if order.status != OrderStatus::Pending {
+            env::panic(b"The order isn't pending of acceptance");
         }
-        
-        let token = &service.metadata.token;
-        // Realizar el pago en NEARs.
-        if token == "near" {
-            if env::attached_deposit() < service.metadata.price {
-                env::panic(b"Insufficient NEARs balance");
-            }
 
-            Promise::new(self.contract_me.clone()).transfer(service.metadata.price);
+        self.internal_settle_milestones(order_id, &order.buyer_id, MilestoneStatus::Refunded);
+        self.internal_finish_order(order_id, OrderStatus::Rejected)
+    }
 
-            // Establecer como servicio vendido y no en venta.
-            service.sold = true;
-            service.on_sale = false;
 
-            // Cambiar propiedad del servicio.
-            service.actual_owner = sender.clone();
-            self.delete_service(&service_id, &service.actual_owner);
-            self.add_service(&service_id, &buyer.account_id);
+    /// Rechazar automaticamente una orden que el profesional no respondio a tiempo.
+    /// Ejecutable por cualquier cuenta, el pago siempre vuelve al empleador.
+    ///
+    /// #Arguments
+    /// * `order_id`
+    pub fn expire_order(&mut self, order_id: u64) -> Order {
+        self.assert_order_exists(&order_id);
 
-            // Establecer tiempo de la compra.
-            service.buy_moment = env::block_timestamp();
+        let order = self.get_order_by_id(order_id.clone());
 
-            self.service_by_id.insert(&service_id, &service);
-        } else {
-            let token = service.metadata.token;
+        if order.status != OrderStatus::Pending {
+            env::panic(b"The order isn't pending of acceptance");
+        }
+        if env::block_timestamp() <= self.internal_acceptance_end(&order) {
+            env::panic(b"The professional still has time to accept the order");
+        }
 
-            if token == self.usdc_contract {
-                let buyer_balance = self.usdc_balances.get(&buyer.account_id).unwrap_or(0);
-                assert!(buyer_balance >= service.metadata.price, "Insufficient USDC balance in the marketplace wallet");
-            }
-            else if token == self.jobs_contract {
-                let buyer_balance = self.jobs_balances.get(&buyer.account_id).unwrap_or(0);
-                assert!(buyer_balance >= service.metadata.price, "Insufficient JOBS balance in the marketplace wallet");
-            } 
-            else {
+        self.internal_settle_milestones(order_id, &order.buyer_id, MilestoneStatus::Refunded);
+        self.internal_finish_order(order_id, OrderStatus::Rejected)
+    }
+
+
+    /// Dar por aprobada una orden por parte del empleador.
+    /// Opcionalmente se agrega una propina, debitada de la wallet del Marketplace del empleador
+    /// y acreditada sin comision en la del profesional. El NEAR adjunto se acredita antes en la wallet.
+    /// La reputacion del profesional se puntua luego con `review_order`.
+    /// 
+    /// #Arguments
+    /// * `order_id`
+    /// * `tip`         - Monto de la propina en la unidad minima del token.
+    /// * `tip_token`   - Token de la propina, por defecto el de la orden.
+    #[payable]
+    pub fn approve_service(&mut self, order_id: u64, tip: Option<U128>, tip_token: Option<AccountId>) {
+        let order = self.get_order_by_id(order_id.clone());
+        let user = env::predecessor_account_id();
+
+        assert!(order.buyer_id == user, "You aren't the buyer");
+        assert!(order.hourly.is_none(), "Hourly contracts are approved by week");
+        assert!(!order.subscription, "Subscriptions are paid by period");
+        assert!(order.status != OrderStatus::OnDispute && order.status != OrderStatus::DisputeRequested,
+            "You already have requested a dispute for this order");
+        assert!(order.is_active(), "The order is already finished");
+
+        let tip = tip.filter(|amount| amount.0 > 0).map(|amount| Tip {
+            token: tip_token.unwrap_or_else(|| order.token.clone()),
+            amount: amount,
+        });
+        self.internal_deposit_attached(&user);
+        if let Some(tip) = &tip {
+            if !self.tokens.contains(&tip.token) {
                 env::panic(b"Token not soported");
-            }        
-
-            // Realizar el pago en el token indicado.
-            ext_contract::ft_transfer(
-                self.contract_me.clone(),
-                (service.metadata.price).into(),
-                None,
-                &token, ONE_YOCTO, GAS_FT_TRANSFER
-            ).then(ext_self::on_buy_service(
-                service_id,
-                &env::current_account_id(), NO_DEPOSIT, BASE_GAS)
-            );
-        };
+            }
+            self.internal_withdraw(&tip.token, &user, tip.amount.0);
+            self.internal_deposit(&tip.token, &order.creator_id, tip.amount.0);
+        }
 
-        NearEvent::log_service_buy(
-            service.id.clone().to_string(),
-            sender.clone().to_string()
+        self.internal_settle_milestones(order_id, &order.creator_id, MilestoneStatus::Released);
+        let mut order = self.internal_finish_order(order_id, OrderStatus::Completed);
+        order.tip = tip;
+        self.order_by_id.insert(&order_id, &order);
+
+        NearEvent::log_order_approve(
+            order_id.to_string(),
+            order.tip.as_ref().map(|tip| tip.token.clone()),
+            order.tip.as_ref().map(|tip| tip.amount.0.to_string()),
         );
     }
 
 
-    /// Dar por aprobado un servicio por parte del empleador.
+    /// Aprobar una etapa de la orden, liberando su parte del escrow al profesional.
+    /// Las etapas se aprueban en orden. Al aprobarse la ultima se completa la orden.
     /// 
-    #[payable]
-    pub fn approve_service(&mut self, service_id: u64, vote: u16) {
-        let service = self.get_service_by_id(service_id.clone());
-        let user = env::predecessor_account_id();
+    /// #Arguments
+    /// * `order_id`
+    /// * `milestone`   - Indice de la etapa a aprobar.
+    pub fn approve_milestone(&mut self, order_id: u64, milestone: u8) -> Order {
+        self.assert_order_exists(&order_id);
 
-        assert!(service.actual_owner == user, "You aren't the owner");
-        assert!(service.on_dispute == false, "You already have requested a dispute for this service");
+        let mut order = self.get_order_by_id(order_id.clone());
 
-        let mut creator = self.get_user(string_to_valid_account_id(&service.creator_id).clone());
-        creator.reputation += vote;
-        creator.votes += 1;
+        assert!(order.buyer_id == env::predecessor_account_id(), "You aren't the buyer");
+        assert!(order.is_active(), "The order isn't in progress");
+        assert!(order.hourly.is_none(), "Hourly contracts are approved by week");
 
-        let _res = ext_mediator::pay_service(
-            env::signer_account_id(),
-            (service.metadata.price*DECIMALS).into(),
-            service.metadata.token.clone(),
-            &self.contract_me,
-            ONE_YOCTO,
-            BASE_GAS,
-        ).then(ext_self::on_return_service(
-            service_id,
-            &env::current_account_id(),
-            NO_DEPOSIT,
-            BASE_GAS,
-        ));
+        let index = milestone as usize;
+        if index >= order.milestones.len() {
+            env::panic(b"The indicated milestone doesn't exist");
+        }
+        if order.milestones[index].status != MilestoneStatus::Pending {
+            env::panic(b"The milestone is already paid");
+        }
+        if order.milestones[..index].iter().any(|m| m.status == MilestoneStatus::Pending) {
+            env::panic(b"Previous milestones must be approved first");
+        }
+
+        let amount = order.milestones[index].amount.0;
+        self.internal_release_escrow(order_id, &order.creator_id, amount);
+
+        order.milestones[index].status = MilestoneStatus::Released;
+        self.order_by_id.insert(&order_id, &order);
+
+        NearEvent::log_order_milestone_approve(
+            order_id.clone().to_string(),
+            milestone.clone().to_string(),
+            amount.to_string()
+        );
+
+        if order.pending_amount() == 0 {
+            order = self.internal_finish_order(order_id, OrderStatus::Completed);
+        }
+        order
     }
 
     
     /// Crear disputa en el contrato mediador.
     /// Solo ejecutable por el empleador que compro el servicio.
+    /// La disputa solo abarca las etapas que aun no fueron pagadas.
     ///
     #[payable]
-    pub fn reclaim_dispute(&mut self, service_id: u64, proves: String) {
+    pub fn reclaim_dispute(&mut self, order_id: u64, proves: String) {
         // Verificar que no haya sido banneado quien solicita la disputa.
         let user_id = string_to_valid_account_id(&env::predecessor_account_id());
         if self.get_user(user_id).banned == true {
             env::panic(b"You are already banned for fraudulent disputes");
         }
-        // Verificar que el servicio exista.
-        self.assert_service_exists(&service_id);
+        // Verificar que la orden exista.
+        self.assert_order_exists(&order_id);
 
-        let service = self.get_service_by_id(service_id.clone());
+        let mut order = self.get_order_by_id(order_id.clone());
 
         // Verificar que efectivamente haya comprado el servicio.
-        if service.actual_owner != env::signer_account_id() || service.actual_owner == service.creator_id {
+        if order.buyer_id != env::signer_account_id() {
             env::panic(b"Only the employeer that buy the service can init a dispute");
         }
         // Verificar que no este ya solicitada la disputa.
-        if service.on_dispute == true {
-            env::panic(b"Actually the service is in dispute");
+        if order.status == OrderStatus::OnDispute || order.status == OrderStatus::DisputeRequested {
+            env::panic(b"Actually the order is in dispute");
         };
+        if !order.is_active() {
+            env::panic(b"The order is already finished");
+        }
+        if order.hourly.is_some() {
+            env::panic(b"Hourly contracts are disputed by week");
+        }
+
+        // Bloquear la orden mientras el mediador crea la disputa, el callback la confirma o la restablece.
+        let previous_status = order.status.clone();
+        order.status = OrderStatus::DisputeRequested;
+        self.order_by_id.insert(&order_id, &order);
 
         let _res = ext_mediator::new_dispute(
-            service_id,
+            order_id,
             env::signer_account_id(),
-            service.creator_id.clone(),
+            order.creator_id.clone(),
             proves,
-            service.metadata.price.clone(),
+            order.pending_amount().into(),
             &self.contract_me,
             env::attached_deposit(),
             BASE_GAS,
         ).then(ext_self::on_new_dispute(
-            service_id,
+            order_id,
+            env::signer_account_id(),
+            env::attached_deposit().into(),
+            previous_status,
             &env::current_account_id(),
             NO_DEPOSIT,
             BASE_GAS,
@@ -371,62 +593,171 @@ impl Marketplace {
     }
 
 
-    /// Retornar un servicio al creador.
-    /// Solo ejecutable por el profesional creador del servicio una vez pasado el tiempo establecido.
-    #[payable]
-    pub fn reclaim_service(&mut self, service_id: u64) {
-        // Verificar que el servicio exista.
-        self.assert_service_exists(&service_id);
+    /// Entregar el trabajo de una orden, iniciando el periodo de revision del empleador.
+    /// Durante ese periodo el empleador puede aprobar o iniciar una disputa.
+    /// Solo ejecutable por el profesional.
+    ///
+    /// #Arguments
+    /// * `order_id`
+    /// * `reference`   - Link o CID del entregable.
+    /// * `hash`        - Hash del entregable.
+    pub fn submit_delivery(&mut self, order_id: u64, reference: String, hash: String) -> Order {
+        self.assert_order_exists(&order_id);
 
-        let service = self.get_service_by_id(service_id.clone());
+        let mut order = self.get_order_by_id(order_id.clone());
 
-        // Verificar que haya pasado el tiempo establecido para poder hacer el reclamo.
-        env::log(format!("Tiempo de liberacion {}", service.buy_moment + ONE_DAY * (service.duration as u64)).as_bytes());
-        if env::block_timestamp() < service.buy_moment + ONE_DAY * (service.duration as u64) / (self.average_block_time/100) {
-            env::panic("Insuficient time to reclame the service".as_bytes());
+        if order.creator_id != env::predecessor_account_id() {
+            env::panic(b"Only the corresponding professional can submit a delivery");
+        }
+        if order.hourly.is_some() {
+            env::panic(b"Hourly contracts are delivered by time logs");
+        }
+        if order.subscription {
+            env::panic(b"Subscriptions are paid by period");
+        }
+        if order.status != OrderStatus::InProgress {
+            env::panic(b"The order isn't in progress");
+        }
+        if reference.len() > 200 || hash.len() > 100 {
+            env::panic(b"Reference max 200 characters and hash max 100 characters");
         }
 
+        order.delivery = Some(Delivery {
+            reference: reference.clone(),
+            hash: hash.clone(),
+            submitted_at: env::block_timestamp(),
+        });
+        order.status = OrderStatus::Delivered;
+        self.order_by_id.insert(&order_id, &order);
+
+        NearEvent::log_order_delivery(
+            order_id.clone().to_string(),
+            reference,
+            hash
+        );
+        NearEvent::log_order_update_status(
+            order_id.clone().to_string(),
+            order.status.to_string()
+        );
+        order
+    }
+
+
+    /// Pedir cambios sobre una entrega, volviendo la orden a InProgress y extendiendo su fecha limite.
+    /// Solo ejecutable por el empleador mientras queden revisiones incluidas.
+    /// Agotadas las revisiones solo puede aprobar o iniciar una disputa.
+    ///
+    /// #Arguments
+    /// * `order_id`
+    /// * `comment`     - Descripcion de los cambios pedidos.
+    pub fn request_revision(&mut self, order_id: u64, comment: String) -> Order {
+        self.assert_order_exists(&order_id);
+
+        let mut order = self.get_order_by_id(order_id.clone());
+
+        if order.buyer_id != env::predecessor_account_id() {
+            env::panic(b"Only the employeer that buy the service can request a revision");
+        }
+        if order.subscription {
+            env::panic(b"Subscriptions are paid by period");
+        }
+        if order.status != OrderStatus::Delivered {
+            env::panic(b"There is no delivery to review");
+        }
+        if !order.has_revisions_left() {
+            env::panic(b"No revisions left, approve the order or open a dispute");
+        }
+        if comment.len() > 400 {
+            env::panic(b"Comment max 400 characters");
+        }
+
+        let now = env::block_timestamp();
+        let extension = ONE_DAY * (self.revision_extension as u64);
+
+        order.revision_requests.push(RevisionRequest {
+            comment: comment.clone(),
+            requested_at: now,
+        });
+        order.status = OrderStatus::InProgress;
+        order.deadline = std::cmp::max(order.deadline, now) + extension;
+        for milestone in order.milestones.iter_mut() {
+            if milestone.status == MilestoneStatus::Pending {
+                milestone.deadline = std::cmp::max(milestone.deadline, now) + extension;
+            }
+        }
+        self.order_by_id.insert(&order_id, &order);
+
+        NearEvent::log_order_revision(
+            order_id.clone().to_string(),
+            comment,
+            order.revision_requests.len().to_string(),
+            order.deadline.clone().to_string()
+        );
+        NearEvent::log_order_update_status(
+            order_id.clone().to_string(),
+            order.status.to_string()
+        );
+        order
+    }
+
+
+    /// Cobrar una orden.
+    /// Solo ejecutable por el profesional una vez entregado el trabajo, si el empleador
+    /// no lo aprobo ni inicio una disputa antes de que termine el periodo de revision.
+    #[payable]
+    pub fn reclaim_service(&mut self, order_id: u64) {
+        // Verificar que la orden exista.
+        self.assert_order_exists(&order_id);
+
+        let order = self.get_order_by_id(order_id.clone());
+
+        // Las suscripciones se cobran por periodo con claim_periods.
+        if order.subscription {
+            env::panic(b"Subscriptions are paid by period");
+        }
         // Verificar que el empleador no haya solicitado una disputa.
-        if service.on_dispute == true {
-            env::panic(b"Actually the service is in dispute");
+        if order.status == OrderStatus::OnDispute || order.status == OrderStatus::DisputeRequested {
+            env::panic(b"Actually the order is in dispute");
+        }
+        if order.status != OrderStatus::Delivered {
+            env::panic(b"The work must be delivered before reclaiming the payment");
+        }
+
+        // Verificar que haya pasado el tiempo establecido para poder hacer el reclamo.
+        let release_moment = self.internal_release_moment(&order);
+        env::log(format!("Tiempo de liberacion {}", release_moment).as_bytes());
+        if env::block_timestamp() < release_moment {
+            env::panic("Insuficient time to reclame the service".as_bytes());
         }
 
         let sender_id = string_to_valid_account_id(&env::predecessor_account_id());
         env::log(sender_id.to_string().as_bytes());
 
-        if service.creator_id != env::signer_account_id() {
+        if order.creator_id != env::signer_account_id() {
             env::panic(b"Only the corresponding professional can reclaim the service");
         }
 
-        let _res = ext_mediator::pay_service(
-            env::signer_account_id(),
-            (service.metadata.price*DECIMALS).into(),
-            service.metadata.token.clone(),
-            &self.contract_me,
-            NO_DEPOSIT,
-            BASE_GAS,
-        ).then(ext_self::on_return_service(
-            service_id,
-            &env::current_account_id(),
-            NO_DEPOSIT,
-            BASE_GAS,
-        ));
+        self.internal_settle_milestones(order_id, &order.creator_id, MilestoneStatus::Released);
+        self.internal_finish_order(order_id, OrderStatus::Completed);
 
         NearEvent::log_service_reclaim(
-            service.id.clone().to_string(),
+            order.id.clone().to_string(),
             sender_id.clone().to_string()
         );
     }
 
 
-    /// Retornar un servicio al creador.
+    /// Devolver el pago de una orden al empleador.
     /// Ejecutable solo por el admin, previa aprobacion de ambas partes.
     /// 
-    pub fn return_service_by_admin(&mut self, service_id: u64) {
-        // Verificar que el servicio exista.
-        self.assert_service_exists(&service_id);
+    pub fn return_service_by_admin(&mut self, order_id: u64) {
+        // Verificar que la orden exista.
+        self.assert_order_exists(&order_id);
 
-        let service = self.get_service_by_id(service_id.clone());
+        let order = self.get_order_by_id(order_id.clone());
+        if !order.is_active() {
+            env::panic(b"The order is already finished or in dispute");
+        }
 
         let sender_id = string_to_valid_account_id(&env::predecessor_account_id());
         env::log(sender_id.to_string().as_bytes());
@@ -435,23 +766,12 @@ impl Marketplace {
             env::panic("Only admins can give back the services".as_bytes());
         }
 
-        let _res = ext_mediator::pay_service(
-            env::signer_account_id(),
-            (service.metadata.price*DECIMALS).into(),
-            service.metadata.token.clone(),
-            &self.contract_me,
-            NO_DEPOSIT,
-            BASE_GAS,
-        ).then(ext_self::on_return_service(
-            service_id,
-            &env::current_account_id(),
-            NO_DEPOSIT,
-            BASE_GAS,
-        ));
+        self.internal_settle_milestones(order_id, &order.buyer_id, MilestoneStatus::Refunded);
+        self.internal_finish_order(order_id, OrderStatus::Returned);
 
         NearEvent::log_service_return(
-            service.id.clone().to_string(),
-            service.creator_id.clone().to_string()
+            order.id.clone().to_string(),
+            order.creator_id.clone().to_string()
         );
     }
 
@@ -462,27 +782,13 @@ impl Marketplace {
     ///
     #[payable]
     pub fn update_service(&mut self, service_id: u64, metadata: ServiceMetadata, duration: u16) -> Service {
-        if metadata.title.len() > 58 {
-            env::panic(b"Title max 58 characters");
-        }
-        else if metadata.title.len() < 10 {
-            env::panic(b"Title min 10 characters");
-        }
+        assert_valid_service_metadata(&metadata);
 
-        if metadata.description.len() > 180 {
-            env::panic(b"Description max 180 characters");
-        }
-        else if metadata.description.len() > 180 {
-            env::panic(b"Description min 10 characters");
-        }
-
-        let categories: Vec<String> = serde_json::from_str(&metadata.categories).unwrap();
-        if categories.len() > 15 {
-            env::panic(b"Max 15 categories");
-        }
-        else if categories.len() < 1 {
-            env::panic(b"Min 1 categories");
+        if !self.tokens.contains(&metadata.token) {
+            env::panic(b"Token not soported");
         }
+        assert_valid_service_terms(&metadata, duration);
+        let (metadata, duration) = with_basic_package(metadata, duration);
 
         let initial_storage_usage = env::storage_usage();
         env::log(format!("initial store usage: {}", initial_storage_usage).as_bytes());
@@ -492,12 +798,8 @@ impl Marketplace {
 
         let mut service = self.get_service_by_id(service_id.clone());
 
-        // Verificar que no este ya comprado.
-        if service.sold == true {
-            env::panic(b"You can't modify while the service is in hands of the employer")
-        }
-
         // Verificar que sea el creador quien ejecuta la funcion.
+        // Las ordenes ya existentes conservan el precio y la duracion con las que se compraron.
         let sender_id = string_to_valid_account_id(&env::predecessor_account_id());
         let sender = self.get_user(sender_id.clone());
         let owner = service.creator_id.clone();
@@ -524,8 +826,9 @@ impl Marketplace {
             service.metadata.title.clone(),
             service.metadata.description.clone(),
             service.metadata.categories.clone(),
-            service.metadata.price.clone().to_string(),
+            service.metadata.price.0.to_string(),
             service.duration.clone().to_string(),
+            serde_json::to_string(&service.metadata.packages).unwrap(),
         );  
         service
     }
@@ -544,16 +847,15 @@ impl Marketplace {
 
         let is_creator = service.creator_id == sender;
 
+        if service.offer.is_some() {
+            env::panic(b"Private offers can't change their sale state");
+        }
+
         // Verificar que sea el cleador.
         if !self.admins.contains(&user.account_id) && !is_creator {
             env::panic("Only the owner or admin can desactivate or activate the service".as_bytes());
         }
 
-        // Verificar que no este ya comprado.
-        if service.sold == true {
-            env::panic(b"You can't modify while the service is in hands of the employer")
-        }
-
         service.on_sale = on_sale;
         self.service_by_id.insert(&service_id, &service);
 
@@ -576,8 +878,10 @@ impl Marketplace {
     /// * `account_id`  - La cuenta de mainnet/testnet de quien sera registrado.
     /// * `roles`        - El rol o roles que tendra el usuario. Solo los admin puenden decir quien es moderador.
     /// * `personal_data`    - Categories y areas las cuales el usuario puede decir a que se dedica.
+    /// * `referrer`    - Usuario que lo refirio, recibe parte de la comision de sus compras.
     #[payable]
-    pub fn add_user(&mut self, is_employee: bool, personal_data: Option<String>) -> User {
+    pub fn add_user(&mut self, is_employee: bool, personal_data: Option<String>, referrer: Option<ValidAccountId>) -> User {
+        self.assert_migrated();
         let initial_storage_usage = env::storage_usage();
         env::log(format!("initial store usage: {}", initial_storage_usage).as_bytes());
 
@@ -615,8 +919,10 @@ impl Marketplace {
 
         let mut new_user = User{
             account_id: account_id.clone(),
-            reputation: 40,
-            votes: 1,
+            reputation: self.reputation_params.prior_score,
+            votes: 0,
+            employer_reputation: self.reputation_params.prior_score,
+            employer_votes: 0,
             is_employee: is_employee,
             is_company: false,
             personal_data: personal_data, 
@@ -627,6 +933,11 @@ impl Marketplace {
             env::panic(b"User account already added");
         }
 
+        if let Some(referrer) = referrer {
+            let referrer = self.internal_check_referrer(referrer.into(), &account_id);
+            self.referrers.insert(&account_id, &referrer);
+        }
+
         let required_storage_in_bytes = env::storage_usage() - initial_storage_usage;
         env::log(format!("New services size in bytes: {}", required_storage_in_bytes).as_bytes());
 
@@ -724,39 +1035,56 @@ impl Marketplace {
     /// 
     pub fn withdraw_ft(&mut self, amount: U128, token: AccountId) -> Balance {
         let sender = env::predecessor_account_id();
-        if token == self.usdc_contract {
-            let actual_balance = self.usdc_balances.get(&sender).unwrap_or(0);
-            assert!(actual_balance >= amount.into(), "Insufficient balance");
-
-            let new_balance = actual_balance - amount.0;
-            self.usdc_balances.insert(&sender, &new_balance);
-
-            ext_contract::ft_transfer(
-                sender.clone(),
-                amount.clone(),
-                None,
-                &token, ONE_YOCTO, GAS_FT_TRANSFER
-            );
-            return new_balance;
-        }
-        else if token == self.jobs_contract {
-            let actual_balance = self.jobs_balances.get(&sender).unwrap_or(0);
-            assert!(actual_balance >= amount.into(), "Insufficient balance");
-
-            let new_balance = actual_balance - amount.0;
-            self.jobs_balances.insert(&sender, &new_balance);
-
-            ext_contract::ft_transfer(
-                sender.clone(),
-                amount.clone(),
-                None,
-                &token, ONE_YOCTO, GAS_FT_TRANSFER
-            );
-            return new_balance;
-        }
-        else {
+        if token == "near" || !self.tokens.contains(&token) {
             env::panic(b"Token not soported");
         }
+
+        let new_balance = self.internal_withdraw(&token, &sender, amount.0);
+
+        ext_contract::ft_transfer(
+            sender.clone(),
+            amount.clone(),
+            None,
+            &token, ONE_YOCTO, GAS_FT_TRANSFER
+        ).then(ext_self::on_transfer(
+            token.clone(),
+            sender,
+            amount,
+            &env::current_account_id(), NO_DEPOSIT, BASE_GAS
+        ));
+        new_balance
+    }
+
+
+    /// Depositar NEAR en la wallet del Marketplace, propia o de otra cuenta.
+    ///
+    /// #Arguments
+    /// * `account_id`  - Cuenta a la que se acredita, por defecto quien deposita.
+    #[payable]
+    pub fn deposit_near(&mut self, account_id: Option<ValidAccountId>) -> U128 {
+        let account_id: AccountId = account_id.map(|id| id.into()).unwrap_or(env::predecessor_account_id());
+        let amount = env::attached_deposit();
+        if amount == 0 {
+            env::panic(b"Attach the NEAR to deposit");
+        }
+        self.internal_deposit(&"near".to_string(), &account_id, amount);
+        self.get_ft_balance_of("near".to_string(), account_id).into()
+    }
+
+
+    /// Hacer withdraw de NEAR de la wallet del Marketplace.
+    /// 
+    pub fn withdraw_near(&mut self, amount: U128) -> Balance {
+        let sender = env::predecessor_account_id();
+        let new_balance = self.internal_withdraw(&"near".to_string(), &sender, amount.0);
+
+        Promise::new(sender.clone()).transfer(amount.0).then(ext_self::on_transfer(
+            "near".to_string(),
+            sender,
+            amount,
+            &env::current_account_id(), NO_DEPOSIT, BASE_GAS
+        ));
+        new_balance
     }
 
 
@@ -765,14 +1093,14 @@ impl Marketplace {
     /*******************************/
 
     /// Agregar nuevo token soportado.
+    /// Se registra junto a su symbol y decimales una vez obtenido su `ft_metadata`.
     /// 
-    pub fn add_token(&mut self, token: ValidAccountId) -> ValidAccountId {
+    pub fn add_token(&mut self, token: ValidAccountId) -> Promise {
         self.assert_owner();
         if self.tokens.contains(&token.to_string()) {
             env::panic(b"Token already added");
         }
-        self.tokens.insert(token.as_ref());
-        token
+        self.internal_register_token(token.as_ref())
     }
 
 
@@ -817,28 +1145,57 @@ impl Marketplace {
     }
 
 
+    /// Modificar los dias del periodo de revision de las entregas.
+    /// 
+    pub fn set_review_period(&mut self, days: u16) {
+        self.assert_owner();
+        self.review_period = days;
+    }
+
+
+    /// Modificar los dias que se extiende una orden con cada revision.
+    /// 
+    pub fn set_revision_extension(&mut self, days: u16) {
+        self.assert_owner();
+        self.revision_extension = days;
+    }
+
+
+    /// Modificar las horas que tiene el profesional para aceptar una orden.
+    /// 
+    pub fn set_acceptance_period(&mut self, hours: u16) {
+        self.assert_owner();
+        self.acceptance_period = hours;
+    }
+
+
     /********************************/
     /****** CALLBACK FUNCTIONS ******/
     /********************************/
 
     /// Verificar datos de usuario desde mediator
+    /// Los profesionales se validan por su reputacion y los empleadores por su reputacion como empleador.
     /// 
     pub fn validate_user(&self, account_id: AccountId) -> bool {
         let user_id = string_to_valid_account_id(&account_id);
         let user = self.get_user(user_id);
 
-        if user.reputation/user.votes < 30 {
+        let role = if user.is_employee { ReputationRole::Professional } else { ReputationRole::Employer };
+        if self.internal_reputation_score(&account_id, &role) < self.reputation_params.min_score {
             env::panic(b"Your reputation isn't sufficient");
         }
         true
     }
     
 
-    /// Callback para retornar un servicio al creador.
-    /// Ejecutable solo el contrator mediador una vez finalizada la disputa.
+    /// Cerrar una orden una vez finalizada la disputa, liberando al ganador las etapas aun no pagadas.
+    /// Ejecutable solo el contrator mediador.
     /// 
-    pub fn return_service_by_mediator(&mut self, service_id: &u64) -> Service {
-        let mut service = self.get_service_by_id(service_id.clone());
+    /// #Arguments
+    /// * `order_id`            - La orden en disputa.
+    /// * `applicant_winner`    - Si el empleador gano la disputa.
+    pub fn return_service_by_mediator(&mut self, order_id: u64, applicant_winner: bool) -> Order {
+        let mut order = self.get_order_by_id(order_id.clone());
 
         // Verificar que sea el contrator mediador quien ejecuta
         let sender_id = env::predecessor_account_id();
@@ -846,17 +1203,26 @@ impl Marketplace {
             env::panic(b"Only mediator contract can execute this function");
         }
 
-        self.delete_service(&service_id, &service.actual_owner);
-        self.add_service(&service_id, &service.creator_id);
+        // En los contratos por hora se disputa una semana, iniciada por el profesional,
+        // mientras la orden sigue en curso.
+        if let Some(week) = order.hourly.as_ref().and_then(|terms| terms.disputed_week) {
+            return self.internal_settle_week(order_id, week, applicant_winner);
+        }
 
-        // Modificar la metadata del servicio
-        service.actual_owner = service.creator_id.clone();
-        service.on_sale = true;
-        service.buy_moment = 0;
-        service.on_dispute = false;
-        self.service_by_id.insert(&service_id, &service);
+        if order.status != OrderStatus::OnDispute {
+            env::panic(b"The order isn't in dispute");
+        }
 
-        service
+        // Pagar al ganador y cerrar la orden.
+        if applicant_winner {
+            self.internal_settle_milestones(order_id, &order.buyer_id, MilestoneStatus::Refunded);
+            order = self.internal_finish_order(order_id, OrderStatus::Returned);
+        } else {
+            self.internal_settle_milestones(order_id, &order.creator_id, MilestoneStatus::Released);
+            order = self.internal_finish_order(order_id, OrderStatus::Completed);
+        }
+
+        order
     }
 
 
@@ -888,10 +1254,591 @@ impl Marketplace {
     }
 
     #[private]
-    fn delete_service(&mut self, service_id: &u64, account_id: &String) {
-        let mut services_set = expect_value_found(self.services_by_account.get(account_id), "Service should be owned by the sender".as_bytes());
-        services_set.remove(service_id);
-        self.services_by_account.insert(&account_id, &services_set);
+    fn add_order(&mut self, order_id: &u64, account_id: &String) {
+        let mut orders_set = self
+            .orders_by_account
+            .get(account_id)
+            .unwrap_or_else(|| UnorderedSet::new(unique_order_prefix(&account_id)));
+        orders_set.insert(order_id);
+        self.orders_by_account.insert(account_id, &orders_set);
     }
 
+    /// Crear una orden a partir del paquete comprado de un servicio, con su precio y duracion actuales.
+    /// Si el paquete no define etapas, la orden tiene una unica etapa por el precio total.
+    #[private]
+    fn internal_new_order(&mut self, service: &Service, package_id: u8, package: &Package, buyer_id: &AccountId, referrer: Option<AccountId>) -> Order {
+        // Las fechas limite se fijan cuando el profesional acepta la orden.
+        let milestones: Vec<Milestone> = if package.milestones.is_empty() {
+            vec![Milestone {
+                description: service.metadata.title.clone(),
+                amount: package.price,
+                duration: package.duration.clone(),
+                deadline: 0,
+                status: MilestoneStatus::Pending,
+            }]
+        } else {
+            package.milestones.iter().map(|m| Milestone {
+                description: m.description.clone(),
+                amount: m.amount,
+                duration: m.duration.clone(),
+                deadline: 0,
+                status: MilestoneStatus::Pending,
+            }).collect()
+        };
+
+        self.internal_insert_order(Order {
+            id: 0,
+            service_id: Some(service.id.clone()),
+            package_id: package_id,
+            job_id: None,
+            buyer_id: buyer_id.clone(),
+            creator_id: service.creator_id.clone(),
+            price: package.price,
+            token: service.metadata.token.clone(),
+            duration: package.duration.clone(),
+            created_at: env::block_timestamp(),
+            buy_moment: 0,
+            deadline: 0,
+            finish_moment: None,
+            status: OrderStatus::Pending,
+            milestones: milestones,
+            delivery: None,
+            revisions: package.revisions.clone(),
+            revision_requests: Vec::new(),
+            cancellation: None,
+            deadline_proposal: None,
+            subscription: false,
+            hourly: None,
+            referrer: referrer,
+            tip: None,
+        })
+    }
+
+    /// Asignar un id a una nueva orden y agregarla a las del comprador y el profesional.
+    /// Sin referidor indicado, se asigna quien refirio al empleador, salvo que sea el profesional.
+    pub(crate) fn internal_insert_order(&mut self, mut order: Order) -> Order {
+        self.total_orders += 1;
+        order.id = self.total_orders.clone();
+
+        if order.referrer.is_none() {
+            order.referrer = self.referrers.get(&order.buyer_id);
+        }
+        if order.referrer.as_ref() == Some(&order.creator_id) {
+            order.referrer = None;
+        }
+
+        if self.order_by_id.insert(&order.id, &order).is_some() {
+            env::panic(b"Order already exists");
+        }
+        self.add_order(&order.id, &order.buyer_id);
+        self.add_order(&order.id, &order.creator_id);
+
+        order
+    }
+
+    /// Verificar que un servicio pueda ser comprado por el empleador, sin modificar el estado.
+    /// Retorna el servicio y el paquete a comprar.
+    #[private]
+    fn internal_check_purchase(&self, service_id: u64, package_id: u8, buyer_id: &AccountId) -> (Service, Package) {
+        // Verificar que el servicio exista.
+        self.assert_service_exists(&service_id);
+
+        let service = self.get_service_by_id(service_id.clone());
+
+        // Verificar que este en venta.
+        if !service.on_sale {
+            env::panic(b"The indicated service is not on sale")
+        }
+
+        // Las ofertas privadas solo las compra su empleador, una unica vez y antes de que venzan.
+        if let Some(offer) = &service.offer {
+            if offer.buyer_id != *buyer_id {
+                env::panic(b"The offer is for another employer");
+            }
+            if env::block_timestamp() > offer.expires_at {
+                env::panic(b"The offer has expired");
+            }
+        }
+
+        // Verificar que no sea el creador.
+        if *buyer_id == service.creator_id {
+            env::panic(b"Can't buy your own service");
+        }
+        if service.metadata.subscription {
+            env::panic(b"The indicated service is a subscription, use subscribe");
+        }
+        if !self.tokens.contains(&service.metadata.token) {
+            env::panic(b"Token not soported");
+        }
+
+        let package = service.package(package_id);
+        (service, package)
+    }
+
+    /// Generar la orden de una compra ya pagada y bloquear su pago en escrow.
+    #[private]
+    fn internal_place_order(&mut self, service: &Service, package_id: u8, package: &Package, buyer_id: &AccountId, referrer: Option<AccountId>) -> Order {
+        // Una oferta privada se compra una unica vez.
+        if service.offer.is_some() {
+            let mut offered = self.get_service_by_id(service.id.clone());
+            offered.on_sale = false;
+            self.service_by_id.insert(&service.id, &offered);
+        }
+
+        let order = self.internal_new_order(service, package_id, package, buyer_id, referrer);
+        self.internal_lock_escrow(&order);
+
+        NearEvent::log_service_buy(
+            service.id.clone().to_string(),
+            order.id.clone().to_string(),
+            buyer_id.clone(),
+            package_id.to_string(),
+        );
+        order
+    }
+
+    /// Momento hasta el cual el profesional puede aceptar una orden.
+    #[private]
+    fn internal_acceptance_end(&self, order: &Order) -> u64 {
+        order.created_at + ONE_HOUR * (self.acceptance_period as u64)
+    }
+
+    /// Momento a partir del cual el profesional puede cobrar una orden entregada.
+    /// No antes de la fecha limite acordada ni antes de que termine el periodo de revision.
+    #[private]
+    fn internal_release_moment(&self, order: &Order) -> u64 {
+        let deadline = order.deadline;
+        let review_end = match &order.delivery {
+            Some(delivery) => delivery.submitted_at + ONE_DAY * (self.review_period as u64),
+            None => env::panic(b"The order hasn't been delivered"),
+        };
+        std::cmp::max(deadline, review_end)
+    }
+
+    /// Pagar o devolver todas las etapas pendientes de una orden.
+    #[private]
+    fn internal_settle_milestones(&mut self, order_id: u64, beneficiary: &AccountId, status: MilestoneStatus) -> Order {
+        let mut order = self.get_order_by_id(order_id.clone());
+
+        for milestone in order.milestones.iter_mut() {
+            if milestone.status == MilestoneStatus::Pending {
+                self.internal_release_escrow(order_id, beneficiary, milestone.amount.0);
+                milestone.status = status.clone();
+            }
+        }
+        self.order_by_id.insert(&order_id, &order);
+        order
+    }
+
+    /// Cerrar una orden con el estado final indicado.
+    #[private]
+    fn internal_finish_order(&mut self, order_id: u64, status: OrderStatus) -> Order {
+        let mut order = self.get_order_by_id(order_id.clone());
+        order.status = status;
+        order.finish_moment = Some(env::block_timestamp());
+        self.order_by_id.insert(&order_id, &order);
+
+        NearEvent::log_order_update_status(
+            order_id.clone().to_string(),
+            order.status.to_string()
+        );
+        order
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use crate::test_utils::*;
+
+    fn two_milestones_order(context: &mut VMContextBuilder, contract: &mut Marketplace) -> u64 {
+        let mut metadata = service_metadata("near");
+        metadata.milestones = vec![
+            MilestoneTerms { description: "Wireframes".to_string(), amount: U128(4_000), duration: 2 },
+            MilestoneTerms { description: "Final design".to_string(), amount: U128(6_000), duration: 5 },
+        ];
+        let service_id = mint_with(context, contract, metadata);
+        let order_id = buy(context, contract, service_id);
+        accept(context, contract, order_id);
+        order_id
+    }
+
+    #[test]
+    fn milestones_release_their_part_of_the_escrow() {
+        let (mut context, mut contract) = setup();
+        let order_id = two_milestones_order(&mut context, &mut contract);
+
+        set_caller(&mut context, 2, 0);
+        let order = contract.approve_milestone(order_id, 0);
+        assert_eq!(order.status, OrderStatus::InProgress);
+        assert_eq!(order.pending_amount(), 6_000);
+        assert_eq!(contract.get_escrow(order_id).released.0, 4_000);
+
+        let order = contract.approve_milestone(order_id, 1);
+        assert_eq!(order.status, OrderStatus::Completed);
+        assert_eq!(contract.get_escrow(order_id).released.0, PRICE);
+    }
+
+    #[test]
+    fn disputes_only_settle_unpaid_milestones() {
+        let (mut context, mut contract) = setup();
+        let order_id = two_milestones_order(&mut context, &mut contract);
+
+        set_caller(&mut context, 2, 0);
+        contract.approve_milestone(order_id, 0);
+        set_on_dispute(&mut contract, order_id);
+
+        set_caller(&mut context, 4, 0);
+        let order = contract.return_service_by_mediator(order_id, true);
+        assert_eq!(order.milestones[0].status, MilestoneStatus::Released);
+        assert_eq!(order.milestones[1].status, MilestoneStatus::Refunded);
+        assert_eq!(contract.get_escrow_total("near".to_string()).0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Previous milestones must be approved first")]
+    fn milestones_are_approved_in_order() {
+        let (mut context, mut contract) = setup();
+        let order_id = two_milestones_order(&mut context, &mut contract);
+
+        set_caller(&mut context, 2, 0);
+        contract.approve_milestone(order_id, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "The milestone is already paid")]
+    fn milestones_are_paid_once() {
+        let (mut context, mut contract) = setup();
+        let order_id = two_milestones_order(&mut context, &mut contract);
+
+        set_caller(&mut context, 2, 0);
+        contract.approve_milestone(order_id, 0);
+        contract.approve_milestone(order_id, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "You aren't the buyer")]
+    fn only_the_buyer_approves_milestones() {
+        let (mut context, mut contract) = setup();
+        let order_id = two_milestones_order(&mut context, &mut contract);
+
+        set_caller(&mut context, 1, 0);
+        contract.approve_milestone(order_id, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "The milestones amounts must add up to the service price")]
+    fn milestones_add_up_to_the_price() {
+        let (mut context, mut contract) = setup();
+        let mut metadata = service_metadata("near");
+        metadata.milestones = vec![
+            MilestoneTerms { description: "Wireframes".to_string(), amount: U128(4_000), duration: 2 },
+        ];
+        mint_with(&mut context, &mut contract, metadata);
+    }
+
+    #[test]
+    fn delivered_orders_are_paid_after_the_deadline() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_order(&mut context, &mut contract);
+        at_day(&mut context, 1);
+        let order = deliver(&mut context, &mut contract, order_id);
+        assert_eq!(order.status, OrderStatus::Delivered);
+
+        // La revision termina el dia 4, pero la duracion acordada el dia 5.
+        at_day(&mut context, 5);
+        set_caller(&mut context, 1, 0);
+        contract.reclaim_service(order_id);
+        assert_eq!(contract.get_order_by_id(order_id).status, OrderStatus::Completed);
+        assert_eq!(contract.get_escrow(order_id).released.0, PRICE);
+    }
+
+    #[test]
+    #[should_panic(expected = "Insuficient time to reclame the service")]
+    fn early_deliveries_wait_for_the_deadline() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_order(&mut context, &mut contract);
+        at_day(&mut context, 1);
+        deliver(&mut context, &mut contract, order_id);
+
+        at_day(&mut context, 4);
+        set_caller(&mut context, 1, 0);
+        contract.reclaim_service(order_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Insuficient time to reclame the service")]
+    fn late_deliveries_wait_for_the_review_period() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_order(&mut context, &mut contract);
+        at_day(&mut context, 4);
+        deliver(&mut context, &mut contract, order_id);
+
+        at_day(&mut context, 6);
+        set_caller(&mut context, 1, 0);
+        contract.reclaim_service(order_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "The work must be delivered before reclaiming the payment")]
+    fn reclaim_requires_a_delivery() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_order(&mut context, &mut contract);
+
+        at_day(&mut context, 30);
+        set_caller(&mut context, 1, 0);
+        contract.reclaim_service(order_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the corresponding professional can submit a delivery")]
+    fn only_the_professional_delivers() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_order(&mut context, &mut contract);
+
+        set_caller(&mut context, 2, 0);
+        contract.submit_delivery(order_id, "ipfs://delivery".to_string(), "hash".to_string());
+    }
+
+    #[test]
+    fn deliveries_can_be_approved_during_the_review() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_order(&mut context, &mut contract);
+        deliver(&mut context, &mut contract, order_id);
+
+        set_caller(&mut context, 2, 0);
+        contract.approve_service(order_id, None, None);
+        assert_eq!(contract.get_order_by_id(order_id).status, OrderStatus::Completed);
+    }
+
+    #[test]
+    fn revisions_reopen_the_order_and_extend_the_deadline() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_order(&mut context, &mut contract);
+        at_day(&mut context, 1);
+        deliver(&mut context, &mut contract, order_id);
+
+        at_day(&mut context, 2);
+        set_caller(&mut context, 2, 0);
+        let order = contract.request_revision(order_id, "Change the colors".to_string());
+        assert_eq!(order.status, OrderStatus::InProgress);
+        assert_eq!(order.deadline, ONE_DAY * 8);
+        assert!(!order.has_revisions_left());
+    }
+
+    #[test]
+    #[should_panic(expected = "No revisions left, approve the order or open a dispute")]
+    fn revisions_are_capped() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_order(&mut context, &mut contract);
+        deliver(&mut context, &mut contract, order_id);
+        set_caller(&mut context, 2, 0);
+        contract.request_revision(order_id, "Change the colors".to_string());
+
+        deliver(&mut context, &mut contract, order_id);
+        set_caller(&mut context, 2, 0);
+        contract.request_revision(order_id, "Change the fonts".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "There is no delivery to review")]
+    fn revisions_require_a_delivery() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_order(&mut context, &mut contract);
+
+        set_caller(&mut context, 2, 0);
+        contract.request_revision(order_id, "Change the colors".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the employeer that buy the service can request a revision")]
+    fn only_the_buyer_requests_revisions() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_order(&mut context, &mut contract);
+        deliver(&mut context, &mut contract, order_id);
+
+        set_caller(&mut context, 1, 0);
+        contract.request_revision(order_id, "Change the colors".to_string());
+    }
+
+    #[test]
+    fn orders_start_when_the_professional_accepts() {
+        let (mut context, mut contract) = setup();
+        let service_id = mint(&mut context, &mut contract);
+        let order_id = buy(&mut context, &mut contract, service_id);
+        assert_eq!(contract.get_order_by_id(order_id).status, OrderStatus::Pending);
+
+        context.block_timestamp(ONE_HOUR * 10);
+        let order = accept(&mut context, &mut contract, order_id);
+        assert_eq!(order.status, OrderStatus::InProgress);
+        assert_eq!(order.deadline, ONE_HOUR * 10 + ONE_DAY * 5);
+    }
+
+    #[test]
+    fn rejected_orders_refund_the_employer() {
+        let (mut context, mut contract) = setup();
+        let service_id = mint(&mut context, &mut contract);
+        let order_id = buy(&mut context, &mut contract, service_id);
+
+        set_caller(&mut context, 1, 0);
+        let order = contract.reject_order(order_id);
+        assert_eq!(order.status, OrderStatus::Rejected);
+        assert_eq!(order.milestones[0].status, MilestoneStatus::Refunded);
+        assert_eq!(contract.get_escrow_total("near".to_string()).0, 0);
+    }
+
+    #[test]
+    fn unanswered_orders_expire() {
+        let (mut context, mut contract) = setup();
+        let service_id = mint(&mut context, &mut contract);
+        let order_id = buy(&mut context, &mut contract, service_id);
+
+        context.block_timestamp(ONE_HOUR * 49);
+        set_caller(&mut context, 3, 0);
+        let order = contract.expire_order(order_id);
+        assert_eq!(order.status, OrderStatus::Rejected);
+        assert_eq!(contract.get_escrow(order_id).released.0, PRICE);
+    }
+
+    #[test]
+    #[should_panic(expected = "The professional still has time to accept the order")]
+    fn orders_dont_expire_early() {
+        let (mut context, mut contract) = setup();
+        let service_id = mint(&mut context, &mut contract);
+        let order_id = buy(&mut context, &mut contract, service_id);
+
+        context.block_timestamp(ONE_HOUR * 47);
+        set_caller(&mut context, 3, 0);
+        contract.expire_order(order_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "The time to accept the order is over")]
+    fn late_acceptances_are_rejected() {
+        let (mut context, mut contract) = setup();
+        let service_id = mint(&mut context, &mut contract);
+        let order_id = buy(&mut context, &mut contract, service_id);
+
+        context.block_timestamp(ONE_HOUR * 49);
+        accept(&mut context, &mut contract, order_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the corresponding professional can accept the order")]
+    fn only_the_professional_accepts() {
+        let (mut context, mut contract) = setup();
+        let service_id = mint(&mut context, &mut contract);
+        let order_id = buy(&mut context, &mut contract, service_id);
+
+        set_caller(&mut context, 2, 0);
+        contract.accept_order(order_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "The order isn't pending of acceptance")]
+    fn orders_are_accepted_once() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_order(&mut context, &mut contract);
+
+        accept(&mut context, &mut contract, order_id);
+    }
+
+    fn package(name: &str, price: Balance, duration: u16, revisions: u8) -> Package {
+        Package {
+            name: name.to_string(),
+            price: U128(price),
+            duration: duration,
+            revisions: revisions,
+            features: vec!["Source files".to_string()],
+            milestones: vec![],
+        }
+    }
+
+    fn packaged_service(context: &mut VMContextBuilder, contract: &mut Marketplace) -> u64 {
+        let mut metadata = service_metadata("near");
+        metadata.packages = vec![package("Basic", PRICE, 5, 1), package("Premium", 2 * PRICE, 10, 3)];
+        mint_with(context, contract, metadata)
+    }
+
+    #[test]
+    fn orders_take_the_terms_of_the_bought_package() {
+        let (mut context, mut contract) = setup();
+        let service_id = packaged_service(&mut context, &mut contract);
+        assert_eq!(contract.get_service_by_id(service_id).metadata.price.0, PRICE);
+
+        let order_id = buy_package(&mut context, &mut contract, service_id, 1);
+        let order = contract.get_order_by_id(order_id);
+        assert_eq!(order.package_id, 1);
+        assert_eq!(order.price.0, 2 * PRICE);
+        assert_eq!(order.duration, 10);
+        assert_eq!(order.revisions, 3);
+        assert_eq!(contract.get_escrow(order_id).amount.0, 2 * PRICE);
+    }
+
+    #[test]
+    #[should_panic(expected = "Insufficient near balance in the marketplace wallet")]
+    fn packages_are_paid_at_their_own_price() {
+        let (mut context, mut contract) = setup();
+        let service_id = packaged_service(&mut context, &mut contract);
+
+        set_caller(&mut context, 2, PRICE);
+        contract.buy_service(service_id, 1, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "The indicated package doesn't exist")]
+    fn unknown_packages_cant_be_bought() {
+        let (mut context, mut contract) = setup();
+        let service_id = packaged_service(&mut context, &mut contract);
+
+        set_caller(&mut context, 2, PRICE);
+        contract.buy_service(service_id, 2, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Max 3 packages")]
+    fn services_have_up_to_three_packages() {
+        let (mut context, mut contract) = setup();
+        let mut metadata = service_metadata("near");
+        metadata.packages = vec![package("Basic", PRICE, 5, 1); 4];
+        mint_with(&mut context, &mut contract, metadata);
+    }
+
+    #[test]
+    fn tips_are_paid_to_the_professional_without_fee() {
+        let (mut context, mut contract) = setup();
+        set_caller(&mut context, 0, 0);
+        contract.set_fee(None, 500);
+        let order_id = place_order(&mut context, &mut contract);
+
+        set_caller(&mut context, 2, 1_000);
+        contract.approve_service(order_id, Some(U128(1_000)), None);
+
+        let fee = PRICE * 500 / 10_000;
+        assert_eq!(near_balance(&contract, 1), PRICE - fee + 1_000);
+        assert_eq!(near_balance(&contract, 2), 0);
+        let tip = contract.get_order_by_id(order_id).tip.unwrap();
+        assert_eq!((tip.token, tip.amount.0), ("near".to_string(), 1_000));
+    }
+
+    #[test]
+    #[should_panic(expected = "Insufficient usdc.near balance in the marketplace wallet")]
+    fn tips_are_debited_from_the_employer_wallet() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_order(&mut context, &mut contract);
+
+        set_caller(&mut context, 2, 0);
+        contract.approve_service(order_id, Some(U128(1_000)), Some(usdc()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Token not soported")]
+    fn tips_are_paid_in_supported_tokens() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_order(&mut context, &mut contract);
+
+        set_caller(&mut context, 2, 0);
+        contract.approve_service(order_id, Some(U128(1_000)), Some("dai.near".to_string()));
+    }
 }
@@ -0,0 +1,464 @@
+use crate::*;
+
+/// Usuario con el formato anterior a la reputacion como empleador.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct OldUser {
+    pub account_id: AccountId,
+    pub reputation: u16,
+    pub votes: u16,
+    pub is_employee: bool,
+    pub is_company: bool,
+    pub personal_data: Option<String>,
+    pub banned: bool,
+}
+
+/// Servicio con el formato anterior a las ordenes, en el que la compra cambiaba su propiedad.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct OldService {
+    pub id: u64,
+    pub metadata: OldServiceMetadata,
+    pub creator_id: AccountId,
+    pub actual_owner: AccountId,
+    pub employers_account_ids: HashSet<AccountId>,
+    pub duration: u16,
+    pub buy_moment: u64,
+    pub sold: bool,
+    pub on_sale: bool,
+    pub on_dispute: bool,
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct OldServiceMetadata {
+    pub title: String,
+    pub description: String,
+    pub categories: String,
+    pub icon: String,
+    pub price: u128,
+    pub token: AccountId,
+}
+
+/// Estado del contrato anterior a la migracion.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct OldMarketplace {
+    pub service_by_id: UnorderedMap<u64, OldService>,
+    pub services_by_account: LookupMap<AccountId, UnorderedSet<u64>>,
+    pub total_services: u64,
+    pub users: UnorderedMap<AccountId, OldUser>,
+    pub owner: AccountId,
+    pub admins: UnorderedSet<AccountId>,
+    pub contract_me: AccountId,
+    pub contract_ft: AccountId,
+    pub tokens: UnorderedSet<AccountId>,
+    pub usdc_contract: AccountId,
+    pub jobs_contract: AccountId,
+    pub usdc_balances: LookupMap<AccountId, Balance>,
+    pub jobs_balances: LookupMap<AccountId, Balance>,
+    pub average_block_time: u64,
+}
+
+/// Registros que quedan con el formato anterior luego de `migrate`.
+/// Los primeros `users` usuarios y `services` servicios de sus mapas aun no se convirtieron.
+/// Mientras tanto las funciones de usuarios y servicios se bloquean con `assert_migrated`.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct LegacyMigration {
+    pub users: u64,
+    pub services: u64,
+    // Contratos de los balances de USDC y JOBS anteriores a la wallet multi token.
+    pub usdc_contract: AccountId,
+    pub jobs_contract: AccountId,
+}
+
+/// Reinterpretar una coleccion con otro tipo de valor, sin tocar sus registros.
+fn retype<T: BorshSerialize, U: BorshDeserialize>(collection: &T) -> U {
+    U::try_from_slice(&collection.try_to_vec().unwrap()).unwrap()
+}
+
+/// Valor ya serializado, para reemplazar un registro sin deserializar el anterior con el formato actual.
+struct RawValue(Vec<u8>);
+
+impl BorshSerialize for RawValue {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        writer.write_all(&self.0)
+    }
+}
+
+impl BorshDeserialize for RawValue {
+    fn deserialize(buf: &mut &[u8]) -> std::io::Result<Self> {
+        let raw = buf.to_vec();
+        *buf = &[];
+        Ok(Self(raw))
+    }
+}
+
+#[near_bindgen]
+impl Marketplace {
+    /// Migrar el estado anterior del contrato al actual.
+    /// Las colecciones nuevas se crean vacias, y los usuarios y servicios existentes se convierten
+    /// luego por partes con `migrate_legacy`. Solo ejecutable por el owner o el propio contrato.
+    #[init(ignore_state)]
+    pub fn migrate() -> Self {
+        let old: OldMarketplace = expect_value_found(env::state_read(), b"The contract isn't initialized");
+
+        let sender = env::predecessor_account_id();
+        if sender != old.owner && sender != env::current_account_id() {
+            env::panic(b"Only the owner can migrate the contract");
+        }
+
+        let mut this = Self {
+            total_services: old.total_services,
+            services_by_account: old.services_by_account,
+            service_by_id: retype(&old.service_by_id),
+            users: retype(&old.users),
+            owner: old.owner,
+            admins: old.admins,
+            contract_me: old.contract_me,
+            contract_ft: old.contract_ft,
+            tokens: old.tokens,
+            balances: LookupMap::new(b"e".to_vec()),
+            balance_totals: LookupMap::new(b"f".to_vec()),
+            average_block_time: old.average_block_time,
+            order_by_id: UnorderedMap::new(b"g".to_vec()),
+            orders_by_account: LookupMap::new(b"h".to_vec()),
+            total_orders: 0,
+            escrows: LookupMap::new(b"i".to_vec()),
+            escrow_totals: UnorderedMap::new(b"j".to_vec()),
+            tokens_info: LookupMap::new(b"k".to_vec()),
+            review_period: 3,
+            revision_extension: 3,
+            cancel_grace_period: 24,
+            acceptance_period: 48,
+            job_by_id: UnorderedMap::new(b"l".to_vec()),
+            jobs_by_account: LookupMap::new(b"m".to_vec()),
+            total_jobs: 0,
+            proposal_by_id: UnorderedMap::new(b"n".to_vec()),
+            proposals_by_job: LookupMap::new(b"p".to_vec()),
+            total_proposals: 0,
+            offers_by_buyer: LookupMap::new(b"s".to_vec()),
+            default_fee: 0,
+            fee_by_token: LookupMap::new(b"v".to_vec()),
+            treasury: UnorderedMap::new(b"w".to_vec()),
+            fee_totals: LookupMap::new(b"x".to_vec()),
+            fees_by_period: LookupMap::new(b"y".to_vec()),
+            referrers: LookupMap::new(b"z".to_vec()),
+            referral_commission: 1000,
+            referral_earnings: LookupMap::new(b"A".to_vec()),
+            referral_totals: LookupMap::new(b"J".to_vec()),
+            review_by_id: LookupMap::new(b"B".to_vec()),
+            review_by_order: LookupMap::new(b"C".to_vec()),
+            employer_review_by_order: LookupMap::new(b"H".to_vec()),
+            reviews_by_service: LookupMap::new(b"D".to_vec()),
+            reviews_by_account: LookupMap::new(b"E".to_vec()),
+            total_reviews: 0,
+            reputation_params: ReputationParams::new(),
+            reputation_by_account: LookupMap::new(b"I".to_vec()),
+            legacy: Some(LegacyMigration {
+                users: old.users.len(),
+                services: old.service_by_id.len(),
+                usdc_contract: old.usdc_contract,
+                jobs_contract: old.jobs_contract,
+            }),
+        };
+
+        // Registrar la metadata de los tokens ya soportados.
+        for token in this.tokens.to_vec() {
+            if token == "near" {
+                this.tokens_info.insert(&token, &TokenInfo {
+                    symbol: "NEAR".to_string(),
+                    decimals: NEAR_DECIMALS,
+                });
+            } else {
+                this.internal_register_token(&token);
+            }
+        }
+        this
+    }
+
+
+    /// Convertir por partes los usuarios y servicios que quedaron con el formato anterior.
+    /// La reputacion y votos de cada usuario se pasan al nuevo calculo y sus balances de USDC y JOBS
+    /// a la wallet del Marketplace. Los servicios vendidos vuelven a listarse en su profesional.
+    /// Los vendidos aun sin pagar pasan a una orden en curso, o en disputa, con su precio en escrow.
+    /// Ese pago lo retenia el Mediator, que lo envia al Marketplace con `forward_legacy_funds`,
+    /// y migra sus disputas con los pares (servicio, orden) del evento de cada compra.
+    /// Solo ejecutable por el owner o admins. Retorna lo que falta convertir, nada si ya se completo.
+    ///
+    /// #Arguments
+    /// * `limit`   - Hasta 50 registros por llamada.
+    pub fn migrate_legacy(&mut self, limit: u64) -> Option<LegacyMigration> {
+        let sender = env::predecessor_account_id();
+        if sender != self.owner && !self.admins.contains(&sender) {
+            env::panic(b"Only the owner or admins can migrate the contract");
+        }
+        let mut legacy = expect_value_found(self.legacy.clone(), b"Nothing left to migrate");
+        let mut limit = std::cmp::min(limit, 50);
+
+        // Se convierten desde el final, asi los pendientes quedan siempre al principio de cada mapa.
+        let old_users: UnorderedMap<AccountId, OldUser> = retype(&self.users);
+        let mut raw_users: UnorderedMap<AccountId, RawValue> = retype(&self.users);
+        let mut usdc_balances: LookupMap<AccountId, Balance> = LookupMap::new(b"e".to_vec());
+        let mut jobs_balances: LookupMap<AccountId, Balance> = LookupMap::new(b"f".to_vec());
+        while limit > 0 && legacy.users > 0 {
+            legacy.users -= 1;
+            limit -= 1;
+            let old = old_users.values_as_vector().get(legacy.users).unwrap();
+            let account_id = old.account_id.clone();
+
+            let mut user = User {
+                account_id: account_id.clone(),
+                reputation: self.reputation_params.prior_score,
+                votes: 0,
+                employer_reputation: self.reputation_params.prior_score,
+                employer_votes: 0,
+                is_employee: old.is_employee,
+                is_company: old.is_company,
+                personal_data: old.personal_data,
+                banned: old.banned,
+            };
+            let reputation = legacy_reputation(old.reputation, old.votes);
+            if reputation.ratings > 0 {
+                user.reputation = self.internal_score(&reputation);
+                user.votes = std::cmp::min(reputation.ratings, u16::MAX as u32) as u16;
+                self.reputation_by_account.insert(&(account_id.clone(), ReputationRole::Professional), &reputation);
+            }
+            raw_users.insert(&account_id, &RawValue(user.try_to_vec().unwrap()));
+
+            // Los balances anteriores se borran antes de acreditarse, ya que comparten prefijo con los nuevos.
+            if let Some(amount) = usdc_balances.remove(&account_id).filter(|amount| *amount > 0) {
+                self.internal_deposit(&legacy.usdc_contract, &account_id, amount);
+            }
+            if let Some(amount) = jobs_balances.remove(&account_id).filter(|amount| *amount > 0) {
+                self.internal_deposit(&legacy.jobs_contract, &account_id, amount);
+            }
+        }
+
+        let old_services: UnorderedMap<u64, OldService> = retype(&self.service_by_id);
+        let mut raw_services: UnorderedMap<u64, RawValue> = retype(&self.service_by_id);
+        while limit > 0 && legacy.services > 0 {
+            legacy.services -= 1;
+            limit -= 1;
+            let old = old_services.values_as_vector().get(legacy.services).unwrap();
+
+            let service = Service {
+                id: old.id.clone(),
+                metadata: ServiceMetadata {
+                    title: old.metadata.title,
+                    description: old.metadata.description,
+                    categories: old.metadata.categories,
+                    icon: old.metadata.icon,
+                    price: old.metadata.price.into(),
+                    token: old.metadata.token,
+                    milestones: Vec::new(),
+                    revisions: 0,
+                    packages: Vec::new(),
+                    subscription: false,
+                },
+                creator_id: old.creator_id.clone(),
+                duration: old.duration,
+                on_sale: old.on_sale,
+                offer: None,
+            };
+            raw_services.insert(&service.id, &RawValue(service.try_to_vec().unwrap()));
+
+            // Los servicios comprados figuraban entre los del empleador.
+            if old.actual_owner != old.creator_id {
+                if let Some(mut services_set) = self.services_by_account.get(&old.actual_owner) {
+                    services_set.remove(&service.id);
+                    self.services_by_account.insert(&old.actual_owner, &services_set);
+                }
+                self.add_service(&service.id, &service.creator_id);
+
+                if old.sold {
+                    self.internal_migrate_sale(&service, &old.actual_owner, old.buy_moment, old.on_dispute);
+                }
+            }
+        }
+
+        if legacy.users == 0 && legacy.services == 0 {
+            self.legacy = None;
+        } else {
+            self.legacy = Some(legacy);
+        }
+        self.legacy.clone()
+    }
+
+
+    /// Crear la orden de un servicio vendido con el formato anterior, ya aceptada desde la compra.
+    #[private]
+    fn internal_migrate_sale(&mut self, service: &Service, buyer_id: &AccountId, buy_moment: u64, on_dispute: bool) -> Order {
+        let deadline = buy_moment + ONE_DAY * (service.duration as u64);
+        let order = self.internal_insert_order(Order {
+            id: 0,
+            service_id: Some(service.id.clone()),
+            package_id: 0,
+            job_id: None,
+            buyer_id: buyer_id.clone(),
+            creator_id: service.creator_id.clone(),
+            price: service.metadata.price,
+            token: service.metadata.token.clone(),
+            duration: service.duration.clone(),
+            created_at: buy_moment,
+            buy_moment: buy_moment,
+            deadline: deadline,
+            finish_moment: None,
+            status: if on_dispute { OrderStatus::OnDispute } else { OrderStatus::InProgress },
+            milestones: vec![Milestone {
+                description: service.metadata.title.clone(),
+                amount: service.metadata.price,
+                duration: service.duration.clone(),
+                deadline: deadline,
+                status: MilestoneStatus::Pending,
+            }],
+            delivery: None,
+            revisions: 0,
+            revision_requests: Vec::new(),
+            cancellation: None,
+            deadline_proposal: None,
+            subscription: false,
+            hourly: None,
+            referrer: None,
+            tip: None,
+        });
+        self.internal_lock_escrow(&order);
+
+        NearEvent::log_service_buy(
+            service.id.clone().to_string(),
+            order.id.clone().to_string(),
+            buyer_id.clone(),
+            "0".to_string(),
+        );
+        order
+    }
+
+
+    /// Usuarios y servicios que aun no se convirtieron desde el formato anterior.
+    pub fn get_legacy_migration(&self) -> Option<LegacyMigration> {
+        self.legacy.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use crate::test_utils::*;
+
+    #[test]
+    #[should_panic(expected = "Nothing left to migrate")]
+    fn new_contracts_have_nothing_to_migrate() {
+        let (mut context, mut contract) = setup();
+        assert!(contract.get_legacy_migration().is_none());
+
+        set_caller(&mut context, 0, 0);
+        contract.migrate_legacy(50);
+    }
+
+    /// Contrato con el formato anterior: un profesional con un servicio en NEAR vendido sin pagar,
+    /// y un empleador con balance de USDC.
+    fn legacy_contract(context: &mut VMContextBuilder) -> Marketplace {
+        context.current_account_id(ValidAccountId::try_from("marketplace.near").unwrap());
+        set_caller(context, 0, 0);
+        let mut old = OldMarketplace {
+            service_by_id: UnorderedMap::new(b"b".to_vec()),
+            services_by_account: LookupMap::new(b"a".to_vec()),
+            total_services: 1,
+            users: UnorderedMap::new(b"c".to_vec()),
+            owner: id(0),
+            admins: UnorderedSet::new(b"c".to_vec()),
+            contract_me: id(4),
+            contract_ft: id(5),
+            tokens: UnorderedSet::new(b"d".to_vec()),
+            usdc_contract: usdc(),
+            jobs_contract: id(5),
+            usdc_balances: LookupMap::new(b"e".to_vec()),
+            jobs_balances: LookupMap::new(b"f".to_vec()),
+            average_block_time: 121,
+        };
+        old.tokens.insert(&"near".to_string());
+        for (index, is_employee) in [(1, true), (2, false)].iter() {
+            old.users.insert(&id(*index), &OldUser {
+                account_id: id(*index),
+                reputation: 0,
+                votes: 0,
+                is_employee: *is_employee,
+                is_company: false,
+                personal_data: None,
+                banned: false,
+            });
+            old.services_by_account.insert(&id(*index), &UnorderedSet::new(unique_prefix(&id(*index))));
+        }
+        old.usdc_balances.insert(&id(2), &(3 * PRICE));
+
+        old.service_by_id.insert(&1, &OldService {
+            id: 1,
+            metadata: OldServiceMetadata {
+                title: "Landing page design".to_string(),
+                description: "Responsive landing page with two revisions".to_string(),
+                categories: "[\"design\"]".to_string(),
+                icon: "".to_string(),
+                price: PRICE,
+                token: "near".to_string(),
+            },
+            creator_id: id(1),
+            actual_owner: id(2),
+            employers_account_ids: HashSet::new(),
+            duration: 5,
+            buy_moment: ONE_DAY,
+            sold: true,
+            on_sale: false,
+            on_dispute: false,
+        });
+        let mut services_set = old.services_by_account.get(&id(2)).unwrap();
+        services_set.insert(&1);
+        old.services_by_account.insert(&id(2), &services_set);
+
+        env::state_write(&old);
+        Marketplace::migrate()
+    }
+
+    #[test]
+    fn legacy_balances_move_to_the_wallet() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = legacy_contract(&mut context);
+
+        assert!(contract.migrate_legacy(50).is_none());
+        assert_eq!(contract.get_ft_balance_of(usdc(), id(2)), 3 * PRICE);
+        assert_eq!(contract.balance_totals.get(&usdc()), Some(3 * PRICE));
+        assert_eq!(contract.get_user(accounts(2)).account_id, id(2));
+    }
+
+    #[test]
+    fn services_sold_in_flight_become_orders() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = legacy_contract(&mut context);
+        contract.migrate_legacy(50);
+
+        let order = contract.get_order_by_id(1);
+        assert_eq!(order.service_id, Some(1));
+        assert_eq!(order.buyer_id, id(2));
+        assert_eq!(order.status, OrderStatus::InProgress);
+        assert_eq!(order.deadline, ONE_DAY * 6);
+        assert_eq!(contract.get_escrow(1).amount.0, PRICE);
+        assert_eq!(contract.get_escrow_total("near".to_string()).0, PRICE);
+        let listed: Vec<u64> = contract.get_user_services(accounts(1), false).iter().map(|s| s.id).collect();
+        assert_eq!(listed, vec![1]);
+    }
+
+    #[test]
+    #[should_panic(expected = "The contract is migrating legacy records, try again later")]
+    fn legacy_records_block_the_users() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = legacy_contract(&mut context);
+
+        set_caller(&mut context, 3, STORAGE_DEPOSIT);
+        contract.add_user(true, None, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner or admins can migrate the contract")]
+    fn only_admins_migrate_legacy_records() {
+        let (mut context, mut contract) = setup();
+
+        set_caller(&mut context, 5, 0);
+        contract.migrate_legacy(50);
+    }
+}
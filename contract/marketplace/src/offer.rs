@@ -0,0 +1,255 @@
+use crate::*;
+
+/// Datos de una oferta privada, un servicio que solo puede comprar un empleador determinado.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PrivateOffer {
+    pub buyer_id: AccountId,
+    pub expires_at: u64,
+    // Deposito por el storage de la oferta, se devuelve al profesional si vence sin comprarse.
+    pub storage_deposit: U128,
+}
+
+#[near_bindgen]
+impl Marketplace {
+    /// Crear una oferta privada para un empleador, con precio y duracion acordados.
+    /// No se lista en `get_services` y solo puede comprarla el empleador indicado mediante `buy_service`.
+    /// Solo ejecutable por profesionales.
+    ///
+    /// #Arguments
+    /// * `buyer_id`    - La cuenta de mainnet/testnet del empleador.
+    /// * `metadata`    - La metadata de la oferta, con el precio acordado.
+    /// * `duration`    - Duracion en dias para realizarse el servicio.
+    /// * `expires_in`  - Dias que el empleador tiene para comprar la oferta.
+    #[payable]
+    pub fn create_offer(&mut self, buyer_id: ValidAccountId, metadata: ServiceMetadata, duration: u16, expires_in: u16) -> Service {
+        let sender = env::predecessor_account_id();
+        let buyer_id: AccountId = buyer_id.into();
+
+        assert_valid_service_metadata(&metadata);
+
+        if !self.tokens.contains(&metadata.token) {
+            env::panic(b"Token not soported");
+        }
+        assert_valid_service_terms(&metadata, duration);
+        let (metadata, duration) = with_basic_package(metadata, duration);
+
+        let user = self.get_user(string_to_valid_account_id(&sender).clone());
+        if !user.is_employee {
+            env::panic(b"Only professionals can create an offer");
+        }
+        if buyer_id == sender {
+            env::panic(b"Can't create an offer for yourself");
+        }
+        if self.users.get(&buyer_id).is_none() {
+            env::panic(b"The buyer isn't registered");
+        }
+        if metadata.subscription {
+            env::panic(b"Private offers can't be subscriptions");
+        }
+        if expires_in == 0 {
+            env::panic(b"The offer must last at least one day");
+        }
+
+        let initial_storage_usage = env::storage_usage();
+
+        self.total_services += 1;
+
+        let mut service = Service {
+            id: self.total_services.clone(),
+            creator_id: sender.clone(),
+            metadata: metadata,
+            duration: duration,
+            on_sale: true,
+            offer: Some(PrivateOffer {
+                buyer_id: buyer_id.clone(),
+                expires_at: env::block_timestamp() + ONE_DAY * (expires_in as u64),
+                storage_deposit: 0.into(),
+            }),
+        };
+        if self.service_by_id.insert(&service.id, &service).is_some() {
+            env::panic(b"Service already exists");
+        }
+        self.add_service(&service.id, &sender);
+
+        let mut offers_set = self
+            .offers_by_buyer
+            .get(&buyer_id)
+            .unwrap_or_else(|| UnorderedSet::new(unique_offer_prefix(&buyer_id)));
+        offers_set.insert(&service.id);
+        self.offers_by_buyer.insert(&buyer_id, &offers_set);
+
+        // Registrar el deposito de storage para poder devolverlo si la oferta vence.
+        let required_storage_in_bytes = env::storage_usage() - initial_storage_usage;
+        let storage_deposit = STORAGE_PRICE_PER_BYTE * Balance::from(required_storage_in_bytes);
+        if let Some(offer) = service.offer.as_mut() {
+            offer.storage_deposit = storage_deposit.into();
+        }
+        self.service_by_id.insert(&service.id, &service);
+
+        NearEvent::log_service_offer(
+            service.id.clone().to_string(),
+            sender,
+            buyer_id,
+            service.metadata.price.0.to_string(),
+            service.duration.clone().to_string(),
+        );
+
+        deposit_refund(required_storage_in_bytes);
+        service
+    }
+
+
+    /// Eliminar una oferta privada vencida sin comprarse, devolviendo su deposito de storage al profesional.
+    /// Ejecutable por cualquier cuenta una vez vencida. El profesional puede retirarla antes.
+    ///
+    /// #Arguments
+    /// * `service_id`
+    pub fn expire_offer(&mut self, service_id: u64) {
+        self.assert_service_exists(&service_id);
+
+        let service = self.get_service_by_id(service_id.clone());
+        let offer = expect_value_found(service.offer.clone(), b"The service isn't a private offer");
+
+        if !service.on_sale {
+            env::panic(b"The offer was already bought");
+        }
+        if env::block_timestamp() <= offer.expires_at && env::predecessor_account_id() != service.creator_id {
+            env::panic(b"The offer hasn't expired yet");
+        }
+
+        self.service_by_id.remove(&service_id);
+        if let Some(mut services_set) = self.services_by_account.get(&service.creator_id) {
+            services_set.remove(&service_id);
+            self.services_by_account.insert(&service.creator_id, &services_set);
+        }
+        if let Some(mut offers_set) = self.offers_by_buyer.get(&offer.buyer_id) {
+            offers_set.remove(&service_id);
+            self.offers_by_buyer.insert(&offer.buyer_id, &offers_set);
+        }
+
+        NearEvent::log_service_update_on_sale(service_id.to_string(), false.to_string());
+
+        if offer.storage_deposit.0 > 0 {
+            Promise::new(service.creator_id.clone()).transfer(offer.storage_deposit.0).then(ext_self::on_transfer(
+                "near".to_string(),
+                service.creator_id,
+                offer.storage_deposit,
+                &env::current_account_id(), NO_DEPOSIT, BASE_GAS
+            ));
+        }
+    }
+
+
+    /// Obtener las ofertas privadas dirigidas a un empleador.
+    ///
+    /// #Arguments
+    /// * `account_id`  - La cuenta de mainnet/testnet del empleador.
+    /// * `only_on_sale`  - Retornar solo las ofertas que aun pueden comprarse.
+    pub fn get_offers_for(&self, account_id: ValidAccountId, only_on_sale: bool) -> Vec<Service> {
+        let now = env::block_timestamp();
+        match self.offers_by_buyer.get(&account_id.into()) {
+            Some(set) => set.iter()
+                .map(|id| expect_value_found(self.service_by_id.get(&id), b"Service id dont match"))
+                .filter(|service| !only_on_sale || (service.on_sale
+                    && service.offer.as_ref().map_or(false, |offer| offer.expires_at >= now)))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use crate::test_utils::*;
+
+    fn offer_to(context: &mut VMContextBuilder, contract: &mut Marketplace, buyer: usize) -> u64 {
+        set_caller(context, 1, STORAGE_DEPOSIT);
+        contract.create_offer(accounts(buyer), service_metadata("near"), 5, 2).id
+    }
+
+    #[test]
+    fn offers_are_bought_once_by_their_employer() {
+        let (mut context, mut contract) = setup();
+        let service_id = offer_to(&mut context, &mut contract, 2);
+        assert_eq!(contract.get_offers_for(accounts(2), true).len(), 1);
+
+        let order_id = buy(&mut context, &mut contract, service_id);
+        assert_eq!(contract.get_order_by_id(order_id).buyer_id, id(2));
+        assert!(!contract.get_service_by_id(service_id).on_sale);
+        assert!(contract.get_offers_for(accounts(2), true).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "The offer is for another employer")]
+    fn offers_are_only_for_their_employer() {
+        let (mut context, mut contract) = setup();
+        let service_id = offer_to(&mut context, &mut contract, 2);
+
+        set_caller(&mut context, 3, PRICE);
+        contract.buy_service(service_id, 0, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "The offer has expired")]
+    fn expired_offers_cant_be_bought() {
+        let (mut context, mut contract) = setup();
+        let service_id = offer_to(&mut context, &mut contract, 2);
+
+        at_day(&mut context, 3);
+        buy(&mut context, &mut contract, service_id);
+    }
+
+    #[test]
+    fn expired_offers_are_removed() {
+        let (mut context, mut contract) = setup();
+        let service_id = offer_to(&mut context, &mut contract, 2);
+
+        at_day(&mut context, 3);
+        set_caller(&mut context, 3, 0);
+        contract.expire_offer(service_id);
+        assert!(contract.service_by_id.get(&service_id).is_none());
+        assert!(contract.get_offers_for(accounts(2), false).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "The offer hasn't expired yet")]
+    fn offers_dont_expire_early() {
+        let (mut context, mut contract) = setup();
+        let service_id = offer_to(&mut context, &mut contract, 2);
+
+        set_caller(&mut context, 3, 0);
+        contract.expire_offer(service_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "The buyer isn't registered")]
+    fn offers_target_registered_employers() {
+        let (mut context, mut contract) = setup();
+
+        offer_to(&mut context, &mut contract, 5);
+    }
+
+    #[test]
+    fn offers_arent_listed_with_public_services() {
+        let (mut context, mut contract) = setup();
+        let public_id = mint(&mut context, &mut contract);
+        offer_to(&mut context, &mut contract, 2);
+
+        let listed: Vec<u64> = contract.get_user_services(accounts(1), false).iter().map(|s| s.id).collect();
+        assert_eq!(listed, vec![public_id]);
+        assert_eq!(contract.get_services(0, 10).len(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Description min 10 characters")]
+    fn offers_share_the_metadata_checks() {
+        let (mut context, mut contract) = setup();
+        let mut metadata = service_metadata("near");
+        metadata.description = "Short".to_string();
+
+        set_caller(&mut context, 1, STORAGE_DEPOSIT);
+        contract.create_offer(accounts(2), metadata, 5, 2);
+    }
+}
@@ -0,0 +1,242 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::json_types::U128;
+use near_sdk::{AccountId};
+use std::fmt::{Display, Formatter, Result};
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum OrderStatus {
+    Pending,    // Pagada, esperando que el profesional la acepte.
+    InProgress, // El profesional esta realizando el trabajo.
+    Delivered,  // El profesional entrego el trabajo y corre el periodo de revision.
+    OnDispute,  // El empleador inicio una disputa en el contrato mediador.
+    Completed,  // Pagado al profesional.
+    Returned,   // Devuelto al empleador.
+    Cancelled,  // Cancelada por el empleador o de comun acuerdo.
+    Rejected,   // Rechazada por el profesional o por no aceptarse a tiempo.
+    DisputeRequested,   // El empleador inicio una disputa, esperando que el mediador la cree.
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum MilestoneStatus {
+    Pending,    // Fondos aun bloqueados en escrow.
+    Released,   // Pagado al profesional.
+    Refunded,   // Devuelto al empleador.
+    Cancelled,  // Repartido entre ambas partes al cancelarse la orden.
+}
+
+/// Entrega del trabajo por parte del profesional.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Delivery {
+    // Link o CID del entregable.
+    pub reference: String,
+    // Hash del entregable para verificar que no fue modificado.
+    pub hash: String,
+    pub submitted_at: u64,
+}
+
+/// Pedido de cambios del empleador sobre una entrega.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RevisionRequest {
+    pub comment: String,
+    pub requested_at: u64,
+}
+
+/// Propuesta de cancelacion de comun acuerdo.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CancellationProposal {
+    pub proposer: AccountId,
+    // Porcentaje de lo pendiente que se devuelve al empleador.
+    pub refund_percentage: u8,
+    pub proposed_at: u64,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum WeekStatus {
+    Open,       // Recibiendo horas del profesional.
+    Contested,  // Objetada por el empleador.
+    OnDispute,  // En disputa en el contrato mediador.
+    Paid,       // Cerrada, con las horas pagadas y el resto devuelto.
+}
+
+/// Horas registradas por el profesional en un contrato por hora.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TimeLog {
+    pub hours: u16,
+    // Detalle de lo realizado o hash del registro.
+    pub memo: String,
+    pub logged_at: u64,
+}
+
+/// Semana de un contrato por hora.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct WorkWeek {
+    pub hours: u16,
+    pub logs: Vec<TimeLog>,
+    pub status: WeekStatus,
+}
+
+/// Terminos de un contrato por hora. Cada semana es una etapa de la orden por el tope de horas.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct HourlyTerms {
+    pub title: String,
+    // Precio por hora en la unidad minima del token.
+    pub rate: U128,
+    pub weekly_cap: u16,
+    pub weeks: Vec<WorkWeek>,
+    // Semana llevada al mediador, si la hay.
+    pub disputed_week: Option<u8>,
+}
+
+/// Propuesta de una nueva duracion para una orden en curso.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DeadlineProposal {
+    pub proposer: AccountId,
+    // Nueva duracion en dias. La fecha limite vigente se corre la diferencia con la actual.
+    pub new_duration: u16,
+    pub proposed_at: u64,
+}
+
+/// Propina del empleador al aprobar una orden, pagada directamente al profesional.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Tip {
+    pub token: AccountId,
+    pub amount: U128,
+}
+
+/// Etapa de un servicio tal como la define el profesional en el listado.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MilestoneTerms {
+    pub description: String,
+    // Monto en la unidad minima del token.
+    pub amount: U128,
+    // Dias desde la compra para entregar la etapa.
+    pub duration: u16,
+}
+
+/// Etapa de una orden, con su fecha limite ya calculada.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Milestone {
+    pub description: String,
+    pub amount: U128,
+    // Dias desde la aceptacion de la orden y fecha limite resultante.
+    pub duration: u16,
+    pub deadline: u64,
+    pub status: MilestoneStatus,
+}
+
+impl Display for WeekStatus {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        match self {
+            WeekStatus::Open => write!(f, "Open"),
+            WeekStatus::Contested => write!(f, "Contested"),
+            WeekStatus::OnDispute => write!(f, "OnDispute"),
+            WeekStatus::Paid => write!(f, "Paid"),
+        }
+    }
+}
+
+impl Display for OrderStatus {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        match self {
+            OrderStatus::Pending => write!(f, "Pending"),
+            OrderStatus::InProgress => write!(f, "InProgress"),
+            OrderStatus::Delivered => write!(f, "Delivered"),
+            OrderStatus::OnDispute => write!(f, "OnDispute"),
+            OrderStatus::Completed => write!(f, "Completed"),
+            OrderStatus::Returned => write!(f, "Returned"),
+            OrderStatus::Cancelled => write!(f, "Cancelled"),
+            OrderStatus::Rejected => write!(f, "Rejected"),
+            OrderStatus::DisputeRequested => write!(f, "DisputeRequested"),
+        }
+    }
+}
+
+/// Compra de un servicio o propuesta aceptada de un trabajo por parte de un empleador.
+/// Un mismo servicio puede tener varias ordenes activas al mismo tiempo.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Order {
+    pub id: u64,
+    // Servicio comprado o trabajo publicado que dio origen a la orden.
+    pub service_id: Option<u64>,
+    // Paquete comprado del servicio.
+    pub package_id: u8,
+    pub job_id: Option<u64>,
+    pub buyer_id: AccountId,
+    pub creator_id: AccountId,
+    // Precio y token al momento de la compra, en la unidad minima del token.
+    pub price: U128,
+    pub token: AccountId,
+    // Dias que va a durar el trabajo.
+    pub duration: u16,
+    // Timestamps del pago, de la aceptacion por parte del profesional y del cierre de la orden.
+    pub created_at: u64,
+    pub buy_moment: u64,
+    // Fecha limite de entrega, se extiende con cada revision.
+    pub deadline: u64,
+    pub finish_moment: Option<u64>,
+    pub status: OrderStatus,
+    // Etapas en orden, cada una se aprueba y paga por separado.
+    pub milestones: Vec<Milestone>,
+    // Ultima entrega del profesional.
+    pub delivery: Option<Delivery>,
+    // Revisiones incluidas en la compra y pedidos de revision realizados.
+    pub revisions: u8,
+    pub revision_requests: Vec<RevisionRequest>,
+    // Propuesta de cancelacion pendiente de aceptar por la otra parte.
+    pub cancellation: Option<CancellationProposal>,
+    // Propuesta de nueva fecha limite pendiente de aceptar por la otra parte.
+    pub deadline_proposal: Option<DeadlineProposal>,
+    // Si cada etapa es un periodo de una suscripcion, que se cobra al terminar.
+    pub subscription: bool,
+    // Solo para contratos por hora.
+    pub hourly: Option<HourlyTerms>,
+    // Cuenta que recibe parte de la comision de la plataforma por haber referido la compra.
+    pub referrer: Option<AccountId>,
+    // Propina agregada por el empleador al aprobar la orden.
+    pub tip: Option<Tip>,
+}
+
+impl Order {
+    /// Si la orden aun no fue cerrada ni esta en disputa.
+    pub fn is_active(&self) -> bool {
+        self.status == OrderStatus::InProgress || self.status == OrderStatus::Delivered
+    }
+
+    /// Si el profesional acepto la orden, la fecha limite se fija recien al aceptarse.
+    pub fn was_accepted(&self) -> bool {
+        self.deadline > 0
+    }
+
+    /// Si un contrato por hora tiene una semana en disputa en el contrato mediador.
+    pub fn has_disputed_week(&self) -> bool {
+        self.hourly.as_ref().map_or(false, |terms| terms.disputed_week.is_some())
+    }
+
+    /// Si el empleador aun puede pedir revisiones.
+    pub fn has_revisions_left(&self) -> bool {
+        self.revision_requests.len() < self.revisions as usize
+    }
+
+    /// Total de las etapas que aun no fueron pagadas ni devueltas.
+    pub fn pending_amount(&self) -> u128 {
+        self.milestones.iter()
+            .filter(|m| m.status == MilestoneStatus::Pending)
+            .map(|m| m.amount.0)
+            .sum()
+    }
+}
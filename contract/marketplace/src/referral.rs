@@ -0,0 +1,246 @@
+use crate::*;
+
+/// Ganancias por referidos de una cuenta en un token.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ReferralEarnings {
+    // Comisiones generadas al liberarse el escrow de las ordenes referidas, aun sin cobrar.
+    pub pending: U128,
+    // Comisiones ya acreditadas en la wallet del Marketplace del referidor.
+    pub paid: U128,
+}
+
+impl ReferralEarnings {
+    pub fn new() -> Self {
+        Self { pending: 0.into(), paid: 0.into() }
+    }
+}
+
+#[near_bindgen]
+impl Marketplace {
+    /// Verificar que una cuenta pueda referir a otra. Retorna el referidor.
+    pub(crate) fn internal_check_referrer(&self, referrer: AccountId, account_id: &AccountId) -> AccountId {
+        if referrer == *account_id {
+            env::panic(b"Can't refer yourself");
+        }
+        if self.users.get(&referrer).is_none() {
+            env::panic(b"The referrer isn't registered");
+        }
+        referrer
+    }
+
+    /// Separar la parte del referidor de la comision cobrada en un pago de una orden.
+    /// Queda pendiente hasta que el referidor la cobre. Retorna lo que le corresponde.
+    pub(crate) fn internal_accrue_referral(&mut self, order_id: u64, token: &AccountId, fee: Balance) -> Balance {
+        let referrer = match self.order_by_id.get(&order_id).and_then(|order| order.referrer) {
+            Some(referrer) => referrer,
+            None => return 0,
+        };
+        let commission = fee * self.referral_commission as u128 / 10_000;
+        if commission == 0 {
+            return 0;
+        }
+
+        let key = (referrer.clone(), token.clone());
+        let mut earnings = self.referral_earnings.get(&key).unwrap_or_else(ReferralEarnings::new);
+        earnings.pending = (earnings.pending.0 + commission).into();
+        self.referral_earnings.insert(&key, &earnings);
+
+        let total = self.referral_totals.get(token).unwrap_or(0);
+        self.referral_totals.insert(token, &(total + commission));
+
+        NearEvent::log_referral_commission(order_id.to_string(), referrer, token.clone(), commission.to_string());
+        commission
+    }
+
+
+    /// Cobrar las comisiones pendientes por referidos de un token.
+    /// Se acreditan en la wallet del Marketplace, desde donde se puede hacer withdraw.
+    ///
+    /// #Arguments
+    /// * `token`   - "near" o la cuenta del contrato del token.
+    pub fn claim_referral_earnings(&mut self, token: AccountId) -> ReferralEarnings {
+        let sender = env::predecessor_account_id();
+        let key = (sender.clone(), token.clone());
+
+        let mut earnings = self.referral_earnings.get(&key).unwrap_or_else(ReferralEarnings::new);
+        let amount = earnings.pending.0;
+        if amount == 0 {
+            env::panic(b"No pending referral earnings");
+        }
+        earnings.pending = 0.into();
+        earnings.paid = (earnings.paid.0 + amount).into();
+        self.referral_earnings.insert(&key, &earnings);
+
+        let total = self.referral_totals.get(&token).unwrap_or(0);
+        self.referral_totals.insert(&token, &(total - amount));
+        self.internal_deposit(&token, &sender, amount);
+
+        NearEvent::log_referral_payout(sender, token, amount.to_string());
+        earnings
+    }
+
+
+    /// Modificar la parte de la comision de la plataforma que recibe el referidor.
+    /// Solo ejecutable por el owner.
+    ///
+    /// #Arguments
+    /// * `commission`  - Puntos basicos sobre la comision, 10000 = toda la comision.
+    pub fn set_referral_commission(&mut self, commission: u16) {
+        self.assert_owner();
+        if commission > 10_000 {
+            env::panic(b"Max commission 10000 basis points");
+        }
+        self.referral_commission = commission;
+    }
+
+
+    /// Quien refirio a un usuario al registrarse.
+    pub fn get_referrer(&self, account_id: ValidAccountId) -> Option<AccountId> {
+        self.referrers.get(account_id.as_ref())
+    }
+
+    /// Parte de la comision de la plataforma que recibe el referidor, en puntos basicos.
+    pub fn get_referral_commission(&self) -> u16 {
+        self.referral_commission
+    }
+
+    /// Ganancias por referidos pendientes y cobradas de una cuenta, por cada token soportado.
+    pub fn get_referral_earnings(&self, account_id: ValidAccountId) -> Vec<(AccountId, ReferralEarnings)> {
+        self.tokens.iter()
+            .filter_map(|token| {
+                self.referral_earnings.get(&(account_id.as_ref().clone(), token.clone()))
+                    .map(|earnings| (token, earnings))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use crate::test_utils::*;
+
+    /// Compra del servicio por el empleador, referida por la cuenta indicada.
+    fn buy_referred(context: &mut VMContextBuilder, contract: &mut Marketplace, service_id: u64, referrer: usize) -> u64 {
+        set_caller(context, 2, PRICE);
+        contract.buy_service(service_id, 0, Some(accounts(referrer)));
+        contract.get_total_orders()
+    }
+
+    #[test]
+    fn referrers_earn_part_of_the_fee() {
+        let (mut context, mut contract) = setup();
+        set_caller(&mut context, 0, 0);
+        contract.set_fee(None, 500);
+        let service_id = mint(&mut context, &mut contract);
+        let order_id = buy_referred(&mut context, &mut contract, service_id, 3);
+        accept(&mut context, &mut contract, order_id);
+
+        set_caller(&mut context, 2, 0);
+        contract.approve_service(order_id, None, None);
+
+        let fee = PRICE * 500 / 10_000;
+        let commission = fee * 1000 / 10_000;
+        assert_eq!(near_balance(&contract, 1), PRICE - fee);
+        assert_eq!(contract.get_treasury(), vec![("near".to_string(), U128(fee - commission))]);
+        let earnings = contract.get_referral_earnings(accounts(3));
+        assert_eq!(earnings[0].1.pending.0, commission);
+
+        set_caller(&mut context, 3, 0);
+        let earnings = contract.claim_referral_earnings("near".to_string());
+        assert_eq!(earnings.pending.0, 0);
+        assert_eq!(earnings.paid.0, commission);
+        assert_eq!(near_balance(&contract, 3), commission);
+    }
+
+    #[test]
+    fn users_referrer_is_used_by_default() {
+        let (mut context, mut contract) = setup();
+        set_caller(&mut context, 5, STORAGE_DEPOSIT);
+        contract.add_user(false, None, Some(accounts(3)));
+        assert_eq!(contract.get_referrer(accounts(5)), Some(id(3)));
+
+        let service_id = mint(&mut context, &mut contract);
+        set_caller(&mut context, 5, PRICE);
+        contract.buy_service(service_id, 0, None);
+
+        let order = contract.get_order_by_id(contract.get_total_orders());
+        assert_eq!(order.referrer, Some(id(3)));
+    }
+
+    #[test]
+    fn the_professional_cant_refer_its_own_orders() {
+        let (mut context, mut contract) = setup();
+        let service_id = mint(&mut context, &mut contract);
+        let order_id = buy_referred(&mut context, &mut contract, service_id, 1);
+
+        assert_eq!(contract.get_order_by_id(order_id).referrer, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Can't refer yourself")]
+    fn buyers_cant_refer_themselves() {
+        let (mut context, mut contract) = setup();
+        let service_id = mint(&mut context, &mut contract);
+
+        buy_referred(&mut context, &mut contract, service_id, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "The referrer isn't registered")]
+    fn referrers_must_be_registered() {
+        let (mut context, mut contract) = setup();
+        let service_id = mint(&mut context, &mut contract);
+
+        buy_referred(&mut context, &mut contract, service_id, 5);
+    }
+
+    #[test]
+    #[should_panic(expected = "No pending referral earnings")]
+    fn claiming_requires_pending_earnings() {
+        let (mut context, mut contract) = setup();
+
+        set_caller(&mut context, 3, 0);
+        contract.claim_referral_earnings("near".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Must be owner_id how call its function")]
+    fn only_the_owner_sets_the_commission() {
+        let (mut context, mut contract) = setup();
+
+        set_caller(&mut context, 1, 0);
+        contract.set_referral_commission(2000);
+    }
+
+    #[test]
+    #[should_panic(expected = "Max commission 10000 basis points")]
+    fn commission_is_capped() {
+        let (mut context, mut contract) = setup();
+
+        set_caller(&mut context, 0, 0);
+        contract.set_referral_commission(10_001);
+    }
+
+    #[test]
+    fn unclaimed_commissions_are_reconciled() {
+        let (mut context, mut contract) = setup();
+        set_caller(&mut context, 0, 0);
+        contract.set_fee(None, 500);
+        let service_id = mint(&mut context, &mut contract);
+        let order_id = buy_referred(&mut context, &mut contract, service_id, 3);
+        accept(&mut context, &mut contract, order_id);
+        set_caller(&mut context, 2, 0);
+        contract.approve_service(order_id, None, None);
+
+        let commission = PRICE * 500 / 10_000 * 1000 / 10_000;
+        assert_eq!(contract.get_near_escrow_reconciliation().referral_total.0, commission);
+
+        set_caller(&mut context, 3, 0);
+        contract.claim_referral_earnings("near".to_string());
+        let reconciliation = contract.get_near_escrow_reconciliation();
+        assert_eq!(reconciliation.referral_total.0, 0);
+        assert_eq!(reconciliation.wallet_total.0, PRICE - PRICE * 500 / 10_000 + commission);
+    }
+}
@@ -0,0 +1,345 @@
+use crate::*;
+
+// Escala de los puntajes, 5 estrellas = 50.
+pub(crate) const MAX_SCORE: u16 = 50;
+// Precision de los pesos, para que el decaimiento no los redondee a cero.
+const WEIGHT_PRECISION: u128 = 1_000_000;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum ReputationRole {
+    Professional,   // Puntuado por los empleadores en las resenias de sus ordenes completadas.
+    Employer,       // Puntuado por los profesionales de sus ordenes completadas.
+}
+
+/// Parametros del calculo de la reputacion, configurables por el owner.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ReputationParams {
+    // Dias en los que el peso de un puntaje se reduce a la mitad. 0 para no decaer.
+    pub half_life: u16,
+    // Puntaje inicial de todo usuario y su peso, en unidades de orden.
+    // A medida que decaen los puntajes recibidos, la reputacion vuelve hacia este valor.
+    pub prior_score: u16,
+    pub prior_weight: u32,
+    // Peso maximo de una orden, en unidades enteras de su token.
+    pub max_order_weight: u32,
+    // Puntaje minimo para ser validado como miembro del jurado.
+    pub min_score: u16,
+}
+
+impl ReputationParams {
+    pub fn new() -> Self {
+        Self {
+            half_life: 180,
+            prior_score: 40,
+            prior_weight: 1,
+            max_order_weight: 100,
+            min_score: 30,
+        }
+    }
+}
+
+/// Puntajes acumulados de un usuario en un rol, ponderados por el valor de cada orden.
+/// Se decaen al momento de agregar un nuevo puntaje o de consultarlos.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ReputationScore {
+    pub weighted_sum: U128,
+    pub total_weight: U128,
+    pub ratings: u32,
+    pub updated_at: u64,
+}
+
+impl ReputationScore {
+    pub fn new() -> Self {
+        Self {
+            weighted_sum: 0.into(),
+            total_weight: 0.into(),
+            ratings: 0,
+            updated_at: env::block_timestamp(),
+        }
+    }
+}
+
+/// Convertir la reputacion y votos del formato anterior al nuevo calculo.
+/// Los 40/1 iniciales se reemplazan por el puntaje inicial configurado
+/// y cada voto restante pesa una unidad de orden.
+pub(crate) fn legacy_reputation(reputation: u16, votes: u16) -> ReputationScore {
+    let ratings = votes.saturating_sub(1) as u128;
+    if ratings == 0 {
+        return ReputationScore::new();
+    }
+    let average = std::cmp::min(reputation.saturating_sub(40) as u128 / ratings, MAX_SCORE as u128);
+    ReputationScore {
+        weighted_sum: average.saturating_mul(ratings).saturating_mul(WEIGHT_PRECISION).into(),
+        total_weight: ratings.saturating_mul(WEIGHT_PRECISION).into(),
+        ratings: ratings as u32,
+        updated_at: env::block_timestamp(),
+    }
+}
+
+/// Detalle del calculo de la reputacion de un usuario.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ReputationBreakdown {
+    pub account_id: AccountId,
+    pub role: ReputationRole,
+    // Reputacion resultante, sobre 50.
+    pub score: u16,
+    pub prior_score: u16,
+    pub prior_weight: U128,
+    // Promedio de los puntajes recibidos, sin el puntaje inicial.
+    pub ratings_average: u16,
+    // Peso de los puntajes recibidos antes y despues de decaer.
+    pub ratings_weight: U128,
+    pub decayed_weight: U128,
+    pub ratings: u32,
+    pub updated_at: u64,
+    // Si alcanza el minimo para ser miembro del jurado.
+    pub eligible: bool,
+}
+
+#[near_bindgen]
+impl Marketplace {
+    /// Agregar el puntaje de una orden a la reputacion de un usuario, ponderado por el valor de la orden.
+    /// Se actualizan tambien la reputacion y cantidad de votos guardadas en el usuario.
+    ///
+    /// #Arguments
+    /// * `account_id`
+    /// * `role`
+    /// * `rating`      - Puntaje del 1 al 5.
+    /// * `order`       - Orden puntuada, de la que se toma el peso.
+    pub(crate) fn internal_rate(&mut self, account_id: &AccountId, role: ReputationRole, rating: u8, order: &Order) -> u16 {
+        let weight = self.internal_order_weight(order);
+        let value = (rating as u128 * 10).min(MAX_SCORE as u128);
+
+        let mut reputation = self.internal_decayed_reputation(account_id, &role);
+        reputation.weighted_sum = reputation.weighted_sum.0.saturating_add(value.saturating_mul(weight)).into();
+        reputation.total_weight = reputation.total_weight.0.saturating_add(weight).into();
+        reputation.ratings = reputation.ratings.saturating_add(1);
+        self.reputation_by_account.insert(&(account_id.clone(), role.clone()), &reputation);
+
+        let score = self.internal_score(&reputation);
+        let votes = std::cmp::min(reputation.ratings, u16::MAX as u32) as u16;
+
+        let mut user = self.get_user(string_to_valid_account_id(account_id).clone());
+        match role {
+            ReputationRole::Professional => {
+                user.reputation = score;
+                user.votes = votes;
+            }
+            ReputationRole::Employer => {
+                user.employer_reputation = score;
+                user.employer_votes = votes;
+            }
+        }
+        self.users.insert(account_id, &user);
+
+        NearEvent::log_user_reputation(
+            account_id.clone(),
+            format!("{:?}", role),
+            score.to_string(),
+            reputation.ratings.to_string(),
+        );
+        score
+    }
+
+    /// Reputacion actual de un usuario en un rol, sobre 50.
+    pub(crate) fn internal_reputation_score(&self, account_id: &AccountId, role: &ReputationRole) -> u16 {
+        let reputation = self.internal_decayed_reputation(account_id, role);
+        self.internal_score(&reputation)
+    }
+
+    /// Puntajes de un usuario decaidos hasta el momento actual.
+    #[private]
+    fn internal_decayed_reputation(&self, account_id: &AccountId, role: &ReputationRole) -> ReputationScore {
+        let mut reputation = self.reputation_by_account
+            .get(&(account_id.clone(), role.clone()))
+            .unwrap_or_else(ReputationScore::new);
+
+        let now = env::block_timestamp();
+        let elapsed = now.saturating_sub(reputation.updated_at);
+        reputation.weighted_sum = self.internal_decay(reputation.weighted_sum.0, elapsed).into();
+        reputation.total_weight = self.internal_decay(reputation.total_weight.0, elapsed).into();
+        reputation.updated_at = now;
+        reputation
+    }
+
+    /// Peso de una orden segun su precio en unidades enteras de su token, entre 1 y el maximo configurado.
+    #[private]
+    fn internal_order_weight(&self, order: &Order) -> u128 {
+        let decimals = self.tokens_info.get(&order.token).map(|info| info.decimals).unwrap_or(0);
+        let unit = 10u128.checked_pow(decimals as u32).unwrap_or(u128::MAX);
+        let units = order.price.0 / unit;
+        units.max(1).min(self.reputation_params.max_order_weight.max(1) as u128) * WEIGHT_PRECISION
+    }
+
+    /// Reducir un valor a la mitad por cada periodo de semivida transcurrido,
+    /// interpolando linealmente dentro del ultimo periodo.
+    #[private]
+    fn internal_decay(&self, value: u128, elapsed: u64) -> u128 {
+        if self.reputation_params.half_life == 0 {
+            return value;
+        }
+        let half_life = ONE_DAY as u128 * self.reputation_params.half_life as u128;
+        let halvings = elapsed as u128 / half_life;
+        if halvings >= 128 {
+            return 0;
+        }
+        let value = value >> halvings;
+        let remainder_bps = (elapsed as u128 % half_life) * 10_000 / half_life;
+        value.saturating_sub(value.saturating_mul(remainder_bps) / 20_000)
+    }
+
+    /// Promedio ponderado de los puntajes recibidos junto al puntaje inicial.
+    pub(crate) fn internal_score(&self, reputation: &ReputationScore) -> u16 {
+        let prior_weight = self.reputation_params.prior_weight as u128 * WEIGHT_PRECISION;
+        let weighted_sum = (self.reputation_params.prior_score as u128)
+            .saturating_mul(prior_weight)
+            .saturating_add(reputation.weighted_sum.0);
+        let total_weight = prior_weight.saturating_add(reputation.total_weight.0);
+        if total_weight == 0 {
+            return self.reputation_params.prior_score;
+        }
+        std::cmp::min(weighted_sum / total_weight, MAX_SCORE as u128) as u16
+    }
+
+
+    /// Modificar los parametros del calculo de la reputacion.
+    /// Solo ejecutable por el owner.
+    ///
+    /// #Arguments
+    /// * `params`  - Semivida en dias, puntaje y peso inicial, peso maximo de una orden y puntaje minimo.
+    pub fn set_reputation_params(&mut self, params: ReputationParams) {
+        self.assert_owner();
+        if params.prior_score > MAX_SCORE || params.min_score > MAX_SCORE {
+            env::panic(b"Max score 50");
+        }
+        if params.max_order_weight == 0 {
+            env::panic(b"The max order weight must be at least 1");
+        }
+        self.reputation_params = params;
+    }
+
+
+    pub fn get_reputation_params(&self) -> ReputationParams {
+        self.reputation_params.clone()
+    }
+
+    /// Detalle de la reputacion de un usuario como profesional o como empleador.
+    ///
+    /// #Arguments
+    /// * `account_id`
+    /// * `employer`    - Si se consulta la reputacion como empleador.
+    pub fn get_reputation(&self, account_id: ValidAccountId, employer: bool) -> ReputationBreakdown {
+        let account_id: AccountId = account_id.into();
+        let role = if employer { ReputationRole::Employer } else { ReputationRole::Professional };
+
+        let stored = self.reputation_by_account
+            .get(&(account_id.clone(), role.clone()))
+            .unwrap_or_else(ReputationScore::new);
+        let reputation = self.internal_decayed_reputation(&account_id, &role);
+        let score = self.internal_score(&reputation);
+
+        ReputationBreakdown {
+            account_id: account_id,
+            role: role,
+            score: score,
+            prior_score: self.reputation_params.prior_score,
+            prior_weight: (self.reputation_params.prior_weight as u128 * WEIGHT_PRECISION).into(),
+            ratings_average: if stored.total_weight.0 == 0 { 0 } else { (stored.weighted_sum.0 / stored.total_weight.0) as u16 },
+            ratings_weight: stored.total_weight,
+            decayed_weight: reputation.total_weight,
+            ratings: stored.ratings,
+            updated_at: stored.updated_at,
+            eligible: score >= self.reputation_params.min_score,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use crate::test_utils::*;
+
+    /// Orden aprobada y puntuada por el empleador.
+    fn rate(context: &mut VMContextBuilder, contract: &mut Marketplace, rating: u8) {
+        let order_id = place_order(context, contract);
+        set_caller(context, 2, 0);
+        contract.approve_service(order_id, None, None);
+        set_caller(context, 2, STORAGE_DEPOSIT);
+        contract.review_order(order_id, rating, "Delivered on time".to_string(), None);
+    }
+
+    #[test]
+    fn new_users_start_at_the_prior_score() {
+        let (_, contract) = setup();
+
+        let reputation = contract.get_reputation(accounts(1), false);
+        assert_eq!((reputation.score, reputation.ratings), (40, 0));
+        assert!(reputation.eligible);
+    }
+
+    #[test]
+    fn ratings_are_weighted_with_the_prior() {
+        let (mut context, mut contract) = setup();
+
+        rate(&mut context, &mut contract, 5);
+
+        let professional = contract.get_user(accounts(1));
+        assert_eq!((professional.reputation, professional.votes), (45, 1));
+        assert_eq!(contract.get_reputation(accounts(1), false).score, 45);
+    }
+
+    #[test]
+    fn ratings_decay_towards_the_prior() {
+        let (mut context, mut contract) = setup();
+        rate(&mut context, &mut contract, 5);
+
+        at_day(&mut context, 180);
+        set_caller(&mut context, 0, 0);
+
+        let reputation = contract.get_reputation(accounts(1), false);
+        assert_eq!(reputation.score, 43);
+        assert_eq!(reputation.decayed_weight.0 * 2, reputation.ratings_weight.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Your reputation isn't sufficient")]
+    fn low_ratings_lose_jury_eligibility() {
+        let (mut context, mut contract) = setup();
+
+        rate(&mut context, &mut contract, 1);
+        contract.validate_user(id(1));
+    }
+
+    #[test]
+    fn legacy_votes_keep_their_average() {
+        let (_, contract) = setup();
+
+        let reputation = legacy_reputation(40 + 45 * 2, 3);
+        assert_eq!((reputation.ratings, contract.internal_score(&reputation)), (2, 43));
+        assert_eq!(legacy_reputation(40, 1).ratings, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Must be owner_id how call its function")]
+    fn only_the_owner_sets_the_params() {
+        let (mut context, mut contract) = setup();
+
+        set_caller(&mut context, 1, 0);
+        contract.set_reputation_params(ReputationParams::new());
+    }
+
+    #[test]
+    #[should_panic(expected = "Max score 50")]
+    fn scores_are_capped() {
+        let (mut context, mut contract) = setup();
+        let mut params = ReputationParams::new();
+        params.min_score = MAX_SCORE + 1;
+
+        set_caller(&mut context, 0, 0);
+        contract.set_reputation_params(params);
+    }
+}
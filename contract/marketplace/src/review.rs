@@ -0,0 +1,378 @@
+use crate::*;
+
+/// Puntajes opcionales por criterio, del 1 al 5.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ReviewCriteria {
+    pub quality: Option<u8>,
+    pub communication: Option<u8>,
+    pub timeliness: Option<u8>,
+}
+
+/// Resenia escrita sobre una orden completada, del empleador al profesional o viceversa.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Review {
+    pub id: u64,
+    pub order_id: u64,
+    pub service_id: Option<u64>,
+    // Quien escribe la resenia y quien la recibe.
+    pub author: AccountId,
+    pub subject: AccountId,
+    // Si la escribe el profesional sobre el empleador.
+    pub about_employer: bool,
+    // Puntaje general del 1 al 5.
+    pub rating: u8,
+    pub comment: String,
+    pub criteria: Option<ReviewCriteria>,
+    pub created_at: u64,
+}
+
+/// Verificar que un puntaje este entre 1 y 5.
+pub(crate) fn assert_valid_rating(rating: u8) {
+    if rating < 1 || rating > 5 {
+        env::panic(b"The rating must be between 1 and 5");
+    }
+}
+
+#[near_bindgen]
+impl Marketplace {
+    /// Dejar una resenia sobre una orden completada, sin importar si se aprobo, se cobro o se resolvio en disputa.
+    /// Solo ejecutable por el empleador, una unica vez por orden. El puntaje suma a la reputacion
+    /// del profesional.
+    ///
+    /// #Arguments
+    /// * `order_id`
+    /// * `rating`      - Puntaje general del 1 al 5.
+    /// * `comment`     - Hasta 400 caracteres.
+    /// * `criteria`    - Puntajes opcionales de calidad, comunicacion y puntualidad.
+    #[payable]
+    pub fn review_order(&mut self, order_id: u64, rating: u8, comment: String, criteria: Option<ReviewCriteria>) -> Review {
+        self.assert_order_exists(&order_id);
+
+        let order = self.get_order_by_id(order_id.clone());
+        if order.buyer_id != env::predecessor_account_id() {
+            env::panic(b"Only the buyer can review the order");
+        }
+        if self.review_by_order.get(&order_id).is_some() {
+            env::panic(b"The order already has a review");
+        }
+
+        let initial_storage_usage = env::storage_usage();
+
+        let review = self.internal_add_review(&order, false, rating, comment, criteria);
+        self.review_by_order.insert(&order_id, &review.id);
+
+        self.internal_rate(&order.creator_id, ReputationRole::Professional, rating, &order);
+
+        if let Some(service_id) = review.service_id {
+            let mut reviews_set = self
+                .reviews_by_service
+                .get(&service_id)
+                .unwrap_or_else(|| UnorderedSet::new(unique_service_reviews_prefix(&service_id)));
+            reviews_set.insert(&review.id);
+            self.reviews_by_service.insert(&service_id, &reviews_set);
+        }
+
+        deposit_refund(env::storage_usage() - initial_storage_usage);
+        review
+    }
+
+
+    /// Puntuar al empleador de una orden completada, con la misma escala que las resenias.
+    /// Solo ejecutable por el profesional, una unica vez por orden. El puntaje suma a la reputacion
+    /// como empleador del comprador.
+    ///
+    /// #Arguments
+    /// * `order_id`
+    /// * `rating`      - Puntaje general del 1 al 5.
+    /// * `comment`     - Hasta 400 caracteres.
+    /// * `criteria`    - Puntajes opcionales de calidad, comunicacion y puntualidad.
+    #[payable]
+    pub fn review_employer(&mut self, order_id: u64, rating: u8, comment: String, criteria: Option<ReviewCriteria>) -> Review {
+        self.assert_order_exists(&order_id);
+
+        let order = self.get_order_by_id(order_id.clone());
+        if order.creator_id != env::predecessor_account_id() {
+            env::panic(b"Only the professional can rate the employer");
+        }
+        if self.employer_review_by_order.get(&order_id).is_some() {
+            env::panic(b"The employer was already rated for this order");
+        }
+
+        let initial_storage_usage = env::storage_usage();
+
+        let review = self.internal_add_review(&order, true, rating, comment, criteria);
+        self.employer_review_by_order.insert(&order_id, &review.id);
+
+        self.internal_rate(&order.buyer_id, ReputationRole::Employer, rating, &order);
+
+        deposit_refund(env::storage_usage() - initial_storage_usage);
+        review
+    }
+
+
+    /// Registrar una resenia de una orden completada y agregarla a las recibidas por la otra parte.
+    pub(crate) fn internal_add_review(&mut self, order: &Order, about_employer: bool, rating: u8, comment: String, criteria: Option<ReviewCriteria>) -> Review {
+        // Tambien se califican las ordenes devueltas o canceladas una vez que el profesional las acepto.
+        match order.status {
+            OrderStatus::Completed | OrderStatus::Returned | OrderStatus::Cancelled => {}
+            _ => env::panic(b"Only closed orders can be reviewed"),
+        }
+        if !order.was_accepted() {
+            env::panic(b"Orders that were never accepted can't be reviewed");
+        }
+        assert_valid_rating(rating);
+        if comment.len() > 400 {
+            env::panic(b"Comment max 400 characters");
+        }
+        if let Some(criteria) = &criteria {
+            for score in [criteria.quality, criteria.communication, criteria.timeliness].iter().flatten() {
+                assert_valid_rating(*score);
+            }
+        }
+
+        let (author, subject) = if about_employer {
+            (order.creator_id.clone(), order.buyer_id.clone())
+        } else {
+            (order.buyer_id.clone(), order.creator_id.clone())
+        };
+
+        self.total_reviews += 1;
+        let review = Review {
+            id: self.total_reviews.clone(),
+            order_id: order.id.clone(),
+            service_id: order.service_id,
+            author: author,
+            subject: subject,
+            about_employer: about_employer,
+            rating: rating,
+            comment: comment,
+            criteria: criteria,
+            created_at: env::block_timestamp(),
+        };
+        self.review_by_id.insert(&review.id, &review);
+
+        let mut reviews_set = self
+            .reviews_by_account
+            .get(&review.subject)
+            .unwrap_or_else(|| UnorderedSet::new(unique_account_reviews_prefix(&review.subject)));
+        reviews_set.insert(&review.id);
+        self.reviews_by_account.insert(&review.subject, &reviews_set);
+
+        NearEvent::log_review_new(
+            review.id.to_string(),
+            review.order_id.to_string(),
+            review.author.clone(),
+            review.subject.clone(),
+            rating.to_string(),
+        );
+        review
+    }
+
+
+    /// Obtener la resenia de una orden, si la tiene.
+    pub fn get_order_review(&self, order_id: u64) -> Option<Review> {
+        self.review_by_order.get(&order_id)
+            .map(|id| expect_value_found(self.review_by_id.get(&id), b"Review id dont match"))
+    }
+
+    /// Obtener el puntaje del profesional al empleador de una orden, si lo tiene.
+    pub fn get_employer_review(&self, order_id: u64) -> Option<Review> {
+        self.employer_review_by_order.get(&order_id)
+            .map(|id| expect_value_found(self.review_by_id.get(&id), b"Review id dont match"))
+    }
+
+    /// Obtener las resenias de un servicio, de la mas antigua a la mas reciente.
+    ///
+    /// #Arguments
+    /// * `service_id`
+    /// * `from_index`
+    /// * `limit`       - Hasta 50 resenias.
+    pub fn get_service_reviews(&self, service_id: u64, from_index: u64, limit: u64) -> Vec<Review> {
+        match self.reviews_by_service.get(&service_id) {
+            Some(set) => self.internal_paginate_reviews(&set, from_index, limit),
+            None => Vec::new(),
+        }
+    }
+
+    /// Obtener las resenias recibidas por un usuario, de la mas antigua a la mas reciente.
+    /// Para los empleadores son los puntajes de los profesionales de sus ordenes.
+    ///
+    /// #Arguments
+    /// * `account_id`
+    /// * `from_index`
+    /// * `limit`       - Hasta 50 resenias.
+    pub fn get_user_reviews(&self, account_id: ValidAccountId, from_index: u64, limit: u64) -> Vec<Review> {
+        match self.reviews_by_account.get(account_id.as_ref()) {
+            Some(set) => self.internal_paginate_reviews(&set, from_index, limit),
+            None => Vec::new(),
+        }
+    }
+
+    /// Obtener el total de resenias.
+    pub fn get_total_reviews(&self) -> u64 {
+        self.total_reviews
+    }
+
+    #[private]
+    fn internal_paginate_reviews(&self, set: &UnorderedSet<u64>, from_index: u64, limit: u64) -> Vec<Review> {
+        let ids = set.as_vector();
+        (from_index..std::cmp::min(from_index + std::cmp::min(limit, 50), ids.len()))
+            .map(|index| expect_value_found(self.review_by_id.get(&ids.get(index).unwrap()), b"Review id dont match"))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use crate::test_utils::*;
+
+    fn completed_order(context: &mut VMContextBuilder, contract: &mut Marketplace) -> u64 {
+        let order_id = place_order(context, contract);
+        set_caller(context, 2, 0);
+        contract.approve_service(order_id, None, None);
+        order_id
+    }
+
+    fn review(context: &mut VMContextBuilder, contract: &mut Marketplace, caller: usize, order_id: u64, rating: u8) -> Review {
+        set_caller(context, caller, STORAGE_DEPOSIT);
+        contract.review_order(order_id, rating, "Delivered on time".to_string(), None)
+    }
+
+    #[test]
+    fn completed_orders_are_reviewed_by_the_employer() {
+        let (mut context, mut contract) = setup();
+        let order_id = completed_order(&mut context, &mut contract);
+        let service_id = contract.get_order_by_id(order_id).service_id.unwrap();
+
+        let review = review(&mut context, &mut contract, 2, order_id, 5);
+
+        assert_eq!(review.subject, id(1));
+        assert_eq!(contract.get_order_review(order_id).unwrap().rating, 5);
+        assert_eq!(contract.get_service_reviews(service_id, 0, 10).len(), 1);
+        assert_eq!(contract.get_user_reviews(accounts(1), 0, 10).len(), 1);
+        assert_eq!(contract.get_total_reviews(), 1);
+        let professional = contract.get_user(accounts(1));
+        assert_eq!((professional.reputation, professional.votes), (45, 1));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the buyer can review the order")]
+    fn only_the_buyer_reviews() {
+        let (mut context, mut contract) = setup();
+        let order_id = completed_order(&mut context, &mut contract);
+
+        review(&mut context, &mut contract, 1, order_id, 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only closed orders can be reviewed")]
+    fn orders_in_progress_cant_be_reviewed() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_order(&mut context, &mut contract);
+
+        review(&mut context, &mut contract, 2, order_id, 4);
+    }
+
+    #[test]
+    fn returned_orders_are_reviewed() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_order(&mut context, &mut contract);
+        set_on_dispute(&mut contract, order_id);
+        set_caller(&mut context, 4, 0);
+        contract.return_service_by_mediator(order_id, true);
+
+        assert_eq!(review(&mut context, &mut contract, 2, order_id, 2).rating, 2);
+    }
+
+    #[test]
+    fn cancelled_orders_are_reviewed_after_the_acceptance() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_order(&mut context, &mut contract);
+        set_caller(&mut context, 2, 0);
+        contract.cancel_order(order_id);
+
+        assert_eq!(review(&mut context, &mut contract, 2, order_id, 3).rating, 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "Orders that were never accepted can't be reviewed")]
+    fn orders_cancelled_before_the_acceptance_cant_be_reviewed() {
+        let (mut context, mut contract) = setup();
+        let service_id = mint(&mut context, &mut contract);
+        let order_id = buy(&mut context, &mut contract, service_id);
+        set_caller(&mut context, 2, 0);
+        contract.cancel_order(order_id);
+
+        review(&mut context, &mut contract, 2, order_id, 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "The order already has a review")]
+    fn orders_are_reviewed_once() {
+        let (mut context, mut contract) = setup();
+        let order_id = completed_order(&mut context, &mut contract);
+
+        review(&mut context, &mut contract, 2, order_id, 4);
+        review(&mut context, &mut contract, 2, order_id, 5);
+    }
+
+    #[test]
+    #[should_panic(expected = "The rating must be between 1 and 5")]
+    fn ratings_are_between_one_and_five() {
+        let (mut context, mut contract) = setup();
+        let order_id = completed_order(&mut context, &mut contract);
+
+        review(&mut context, &mut contract, 2, order_id, 6);
+    }
+
+    #[test]
+    #[should_panic(expected = "The rating must be between 1 and 5")]
+    fn criteria_are_between_one_and_five() {
+        let (mut context, mut contract) = setup();
+        let order_id = completed_order(&mut context, &mut contract);
+
+        set_caller(&mut context, 2, STORAGE_DEPOSIT);
+        let criteria = ReviewCriteria { quality: Some(5), communication: Some(0), timeliness: None };
+        contract.review_order(order_id, 4, "Delivered on time".to_string(), Some(criteria));
+    }
+
+    #[test]
+    fn professionals_rate_the_employer() {
+        let (mut context, mut contract) = setup();
+        let order_id = completed_order(&mut context, &mut contract);
+
+        set_caller(&mut context, 1, STORAGE_DEPOSIT);
+        let review = contract.review_employer(order_id, 2, "Slow feedback".to_string(), None);
+
+        assert!(review.about_employer);
+        assert_eq!(review.subject, id(2));
+        assert_eq!(contract.get_employer_review(order_id).unwrap().rating, 2);
+        let employer = contract.get_user(accounts(2));
+        assert_eq!((employer.employer_reputation, employer.employer_votes), (30, 1));
+        assert_eq!(contract.get_user_reviews(accounts(2), 0, 10).len(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the professional can rate the employer")]
+    fn only_the_professional_rates_the_employer() {
+        let (mut context, mut contract) = setup();
+        let order_id = completed_order(&mut context, &mut contract);
+
+        set_caller(&mut context, 2, STORAGE_DEPOSIT);
+        contract.review_employer(order_id, 5, "Great client".to_string(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "The employer was already rated for this order")]
+    fn employers_are_rated_once_per_order() {
+        let (mut context, mut contract) = setup();
+        let order_id = completed_order(&mut context, &mut contract);
+
+        set_caller(&mut context, 1, STORAGE_DEPOSIT);
+        contract.review_employer(order_id, 5, "Great client".to_string(), None);
+        contract.review_employer(order_id, 1, "Changed my mind".to_string(), None);
+    }
+}
@@ -0,0 +1,331 @@
+use crate::*;
+
+#[near_bindgen]
+impl Marketplace {
+    /// Suscribirse a un servicio recurrente, prefinanciando varios periodos desde la wallet del Marketplace.
+    /// Cada periodo dura lo que el paquete y se paga al profesional al terminar.
+    /// La orden queda pendiente de aceptacion como cualquier compra.
+    ///
+    /// #Arguments
+    /// * `service_id`
+    /// * `package_id`  - Indice del paquete a contratar, 0 si el servicio no define paquetes.
+    /// * `periods`     - Cantidad de periodos a prefinanciar.
+    pub fn subscribe(&mut self, service_id: u64, package_id: u8, periods: u8) -> Order {
+        self.assert_service_exists(&service_id);
+
+        let service = self.get_service_by_id(service_id.clone());
+        let sender = env::predecessor_account_id();
+
+        if !service.on_sale {
+            env::panic(b"The indicated service is not on sale");
+        }
+        if !service.metadata.subscription {
+            env::panic(b"The indicated service isn't a subscription, use buy_service");
+        }
+        if !self.tokens.contains(&service.metadata.token) {
+            env::panic(b"Token not soported");
+        }
+        if service.creator_id == sender {
+            env::panic(b"Can't buy your own service");
+        }
+        if periods < 1 || periods > 24 {
+            env::panic(b"Between 1 and 24 periods");
+        }
+        let buyer = self.get_user(string_to_valid_account_id(&sender).clone());
+
+        let package = service.package(package_id);
+        let total_duration = package.duration as u64 * periods as u64;
+        if total_duration > u16::MAX as u64 {
+            env::panic(b"The subscription is too long");
+        }
+
+        // Los periodos se pagan desde el balance interno y quedan bloqueados en escrow.
+        let amount = package.price.0 * periods as u128;
+        self.internal_withdraw(&service.metadata.token, &buyer.account_id, amount);
+
+        // Cada periodo es una etapa, sus fechas limite se fijan al aceptarse la orden.
+        let milestones: Vec<Milestone> = (1..=periods).map(|period| Milestone {
+            description: format!("Period {}", period),
+            amount: package.price,
+            duration: package.duration * period as u16,
+            deadline: 0,
+            status: MilestoneStatus::Pending,
+        }).collect();
+
+        let order = self.internal_insert_order(Order {
+            id: 0,
+            service_id: Some(service.id.clone()),
+            package_id: package_id,
+            job_id: None,
+            buyer_id: buyer.account_id.clone(),
+            creator_id: service.creator_id.clone(),
+            price: amount.into(),
+            token: service.metadata.token.clone(),
+            duration: total_duration as u16,
+            created_at: env::block_timestamp(),
+            buy_moment: 0,
+            deadline: 0,
+            finish_moment: None,
+            status: OrderStatus::Pending,
+            milestones: milestones,
+            delivery: None,
+            revisions: package.revisions.clone(),
+            revision_requests: Vec::new(),
+            cancellation: None,
+            deadline_proposal: None,
+            subscription: true,
+            hourly: None,
+            referrer: None,
+            tip: None,
+        });
+        self.internal_lock_escrow(&order);
+
+        NearEvent::log_service_buy(
+            service.id.clone().to_string(),
+            order.id.clone().to_string(),
+            sender,
+            package_id.to_string(),
+        );
+        order
+    }
+
+
+    /// Cobrar los periodos ya terminados de una suscripcion.
+    /// Solo ejecutable por el profesional. Al cobrarse el ultimo periodo se completa la orden.
+    ///
+    /// #Arguments
+    /// * `order_id`
+    pub fn claim_periods(&mut self, order_id: u64) -> Order {
+        self.assert_order_exists(&order_id);
+
+        let order = self.get_order_by_id(order_id.clone());
+
+        if order.creator_id != env::predecessor_account_id() {
+            env::panic(b"Only the corresponding professional can claim the periods");
+        }
+        if !order.subscription {
+            env::panic(b"The order isn't a subscription");
+        }
+        if !order.is_active() {
+            env::panic(b"The order isn't in progress");
+        }
+
+        let pending = order.pending_amount();
+        let order = self.internal_release_ended_periods(order_id);
+        if order.pending_amount() == pending {
+            env::panic(b"No period has ended yet");
+        }
+
+        if order.pending_amount() == 0 {
+            return self.internal_finish_order(order_id, OrderStatus::Completed);
+        }
+        order
+    }
+
+
+    /// Cancelar una suscripcion. Se pagan al profesional los periodos ya terminados
+    /// y se devuelven al empleador los restantes.
+    ///
+    /// #Arguments
+    /// * `order_id`
+    pub fn cancel_subscription(&mut self, order_id: u64) -> Order {
+        self.assert_order_exists(&order_id);
+
+        let order = self.get_order_by_id(order_id.clone());
+
+        if order.buyer_id != env::predecessor_account_id() {
+            env::panic(b"Only the employeer that subscribed can cancel the subscription");
+        }
+        if !order.subscription {
+            env::panic(b"The order isn't a subscription");
+        }
+        if order.status != OrderStatus::Pending && !order.is_active() {
+            env::panic(b"The order is already finished or in dispute");
+        }
+
+        // Una orden sin aceptar no tiene periodos terminados.
+        if order.status != OrderStatus::Pending {
+            self.internal_release_ended_periods(order_id);
+        }
+        self.internal_settle_milestones(order_id, &order.buyer_id, MilestoneStatus::Refunded);
+        self.internal_finish_order(order_id, OrderStatus::Cancelled)
+    }
+
+
+    /// Pagar al profesional los periodos pendientes cuya fecha limite ya paso.
+    pub(crate) fn internal_release_ended_periods(&mut self, order_id: u64) -> Order {
+        let mut order = self.get_order_by_id(order_id.clone());
+        let now = env::block_timestamp();
+
+        for (index, milestone) in order.milestones.iter_mut().enumerate() {
+            if milestone.status == MilestoneStatus::Pending && milestone.deadline <= now {
+                self.internal_release_escrow(order_id, &order.creator_id, milestone.amount.0);
+                milestone.status = MilestoneStatus::Released;
+
+                NearEvent::log_order_milestone_approve(
+                    order_id.clone().to_string(),
+                    index.to_string(),
+                    milestone.amount.0.to_string()
+                );
+            }
+        }
+        self.order_by_id.insert(&order_id, &order);
+        order
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use crate::test_utils::*;
+
+    /// Suscripcion de tres periodos de 5 dias en USDC, ya aceptada por el profesional.
+    fn subscribe(context: &mut VMContextBuilder, contract: &mut Marketplace) -> u64 {
+        let mut metadata = service_metadata("usdc.near");
+        metadata.subscription = true;
+        let service_id = mint_with(context, contract, metadata);
+        contract.internal_deposit(&usdc(), &id(2), 3 * PRICE);
+
+        set_caller(context, 2, 0);
+        let order_id = contract.subscribe(service_id, 0, 3).id;
+        accept(context, contract, order_id);
+        order_id
+    }
+
+    #[test]
+    fn ended_periods_are_paid_to_the_professional() {
+        let (mut context, mut contract) = setup();
+        let order_id = subscribe(&mut context, &mut contract);
+        assert_eq!(contract.get_ft_balance_of(usdc(), id(2)), 0);
+        assert_eq!(contract.get_escrow(order_id).amount.0, 3 * PRICE);
+
+        at_day(&mut context, 11);
+        set_caller(&mut context, 1, 0);
+        let order = contract.claim_periods(order_id);
+        assert_eq!(order.status, OrderStatus::InProgress);
+        assert_eq!(order.pending_amount(), PRICE);
+        assert_eq!(contract.get_ft_balance_of(usdc(), id(1)), 2 * PRICE);
+
+        at_day(&mut context, 15);
+        set_caller(&mut context, 1, 0);
+        let order = contract.claim_periods(order_id);
+        assert_eq!(order.status, OrderStatus::Completed);
+        assert_eq!(contract.get_ft_balance_of(usdc(), id(1)), 3 * PRICE);
+    }
+
+    #[test]
+    fn cancelling_refunds_the_remaining_periods() {
+        let (mut context, mut contract) = setup();
+        let order_id = subscribe(&mut context, &mut contract);
+
+        at_day(&mut context, 6);
+        set_caller(&mut context, 2, 0);
+        let order = contract.cancel_subscription(order_id);
+        assert_eq!(order.status, OrderStatus::Cancelled);
+        assert_eq!(contract.get_ft_balance_of(usdc(), id(1)), PRICE);
+        assert_eq!(contract.get_ft_balance_of(usdc(), id(2)), 2 * PRICE);
+        assert_eq!(contract.get_escrow_total(usdc()).0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "No period has ended yet")]
+    fn periods_are_claimed_once_ended() {
+        let (mut context, mut contract) = setup();
+        let order_id = subscribe(&mut context, &mut contract);
+
+        at_day(&mut context, 4);
+        set_caller(&mut context, 1, 0);
+        contract.claim_periods(order_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the corresponding professional can claim the periods")]
+    fn only_the_professional_claims_periods() {
+        let (mut context, mut contract) = setup();
+        let order_id = subscribe(&mut context, &mut contract);
+
+        at_day(&mut context, 5);
+        set_caller(&mut context, 2, 0);
+        contract.claim_periods(order_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Insufficient usdc.near balance in the marketplace wallet")]
+    fn subscriptions_are_prefunded() {
+        let (mut context, mut contract) = setup();
+        let mut metadata = service_metadata("usdc.near");
+        metadata.subscription = true;
+        let service_id = mint_with(&mut context, &mut contract, metadata);
+        contract.internal_deposit(&usdc(), &id(2), 2 * PRICE);
+
+        set_caller(&mut context, 2, 0);
+        contract.subscribe(service_id, 0, 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "The indicated service is a subscription, use subscribe")]
+    fn subscriptions_arent_bought_as_services() {
+        let (mut context, mut contract) = setup();
+        let mut metadata = service_metadata("usdc.near");
+        metadata.subscription = true;
+        let service_id = mint_with(&mut context, &mut contract, metadata);
+        contract.internal_deposit(&usdc(), &id(2), PRICE);
+
+        buy(&mut context, &mut contract, service_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Hourly contracts and subscriptions keep their deadlines")]
+    fn subscriptions_keep_their_deadline() {
+        let (mut context, mut contract) = setup();
+        let order_id = subscribe(&mut context, &mut contract);
+
+        set_caller(&mut context, 2, 0);
+        contract.accept_deadline(order_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Subscriptions are paid by period")]
+    fn subscriptions_arent_reclaimed() {
+        let (mut context, mut contract) = setup();
+        let order_id = subscribe(&mut context, &mut contract);
+
+        at_day(&mut context, 16);
+        set_caller(&mut context, 1, 0);
+        contract.reclaim_service(order_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Subscriptions are paid by period")]
+    fn subscriptions_arent_delivered() {
+        let (mut context, mut contract) = setup();
+        let order_id = subscribe(&mut context, &mut contract);
+
+        set_caller(&mut context, 1, 0);
+        contract.submit_delivery(order_id, "ipfs://period".to_string(), "hash".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Subscriptions are paid by period")]
+    fn subscriptions_arent_approved() {
+        let (mut context, mut contract) = setup();
+        let order_id = subscribe(&mut context, &mut contract);
+
+        set_caller(&mut context, 2, 0);
+        contract.approve_service(order_id, None, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Token not soported")]
+    fn subscriptions_require_a_supported_token() {
+        let (mut context, mut contract) = setup();
+        let mut metadata = service_metadata("usdc.near");
+        metadata.subscription = true;
+        let service_id = mint_with(&mut context, &mut contract, metadata);
+
+        contract.tokens.remove(&usdc());
+        contract.internal_deposit(&usdc(), &id(2), PRICE);
+        set_caller(&mut context, 2, 0);
+        contract.subscribe(service_id, 0, 1);
+    }
+}
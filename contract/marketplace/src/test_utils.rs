@@ -0,0 +1,142 @@
+use crate::*;
+pub(crate) use near_sdk::test_utils::{accounts, VMContextBuilder};
+pub(crate) use near_sdk::{testing_env, MockedBlockchain, RuntimeFeesConfig, VMConfig};
+
+// Cuentas usadas en los tests:
+// accounts(0) owner, accounts(1) profesional, accounts(2) empleador,
+// accounts(3) otro profesional, accounts(4) contrato mediador, accounts(5) contrato FT.
+pub(crate) const PRICE: Balance = 10_000;
+pub(crate) const STORAGE_DEPOSIT: Balance = 1_000_000_000_000_000_000_000_000;
+
+pub(crate) fn id(index: usize) -> AccountId {
+    accounts(index).into()
+}
+
+/// Cambiar quien ejecuta y el deposito adjunto, manteniendo el storage del contrato.
+pub(crate) fn set_caller(context: &mut VMContextBuilder, index: usize, deposit: Balance) {
+    testing_env!(context
+        .predecessor_account_id(accounts(index))
+        .signer_account_id(accounts(index))
+        .attached_deposit(deposit)
+        .build());
+}
+
+/// Ejecutar como callback del propio contrato, con el resultado de la promesa indicado.
+pub(crate) fn set_callback(context: &mut VMContextBuilder, result: PromiseResult) {
+    testing_env!(
+        context
+            .predecessor_account_id(ValidAccountId::try_from("marketplace.near").unwrap())
+            .attached_deposit(0)
+            .build(),
+        VMConfig::default(),
+        RuntimeFeesConfig::default(),
+        Default::default(),
+        vec![result]
+    );
+}
+
+/// Marketplace con USDC registrado, dos profesionales y un empleador.
+pub(crate) fn setup() -> (VMContextBuilder, Marketplace) {
+    let mut context = VMContextBuilder::new();
+    context.current_account_id(ValidAccountId::try_from("marketplace.near").unwrap());
+    set_caller(&mut context, 0, 0);
+    let mut contract = Marketplace::new(
+        accounts(0),
+        accounts(4),
+        accounts(5),
+        ValidAccountId::try_from("usdc.near").unwrap(),
+        None,
+    );
+    set_callback(&mut context, PromiseResult::Successful(b"{\"symbol\":\"USDC\",\"decimals\":6}".to_vec()));
+    contract.on_add_token(usdc());
+
+    for (index, is_employee) in [(1, true), (2, false), (3, true)].iter() {
+        set_caller(&mut context, *index, STORAGE_DEPOSIT);
+        contract.add_user(*is_employee, None, None);
+    }
+    (context, contract)
+}
+
+pub(crate) fn service_metadata(token: &str) -> ServiceMetadata {
+    ServiceMetadata {
+        title: "Landing page design".to_string(),
+        description: "Responsive landing page with two revisions".to_string(),
+        categories: "[\"design\"]".to_string(),
+        icon: "".to_string(),
+        price: U128(PRICE),
+        token: token.to_string(),
+        milestones: vec![],
+        revisions: 1,
+        packages: vec![],
+        subscription: false,
+    }
+}
+
+/// Servicio en NEAR minteado por el profesional.
+pub(crate) fn mint(context: &mut VMContextBuilder, contract: &mut Marketplace) -> u64 {
+    mint_in(context, contract, "near")
+}
+
+/// Servicio minteado por el profesional con precio en el token indicado.
+pub(crate) fn mint_in(context: &mut VMContextBuilder, contract: &mut Marketplace, token: &str) -> u64 {
+    mint_with(context, contract, service_metadata(token))
+}
+
+pub(crate) fn mint_with(context: &mut VMContextBuilder, contract: &mut Marketplace, metadata: ServiceMetadata) -> u64 {
+    set_caller(context, 1, STORAGE_DEPOSIT);
+    contract.mint_service(metadata, 5).id
+}
+
+/// Compra del servicio por el empleador, devuelve la orden creada.
+/// Los servicios en FT se pagan desde su wallet del Marketplace.
+pub(crate) fn buy(context: &mut VMContextBuilder, contract: &mut Marketplace, service_id: u64) -> u64 {
+    buy_package(context, contract, service_id, 0)
+}
+
+pub(crate) fn buy_package(context: &mut VMContextBuilder, contract: &mut Marketplace, service_id: u64, package_id: u8) -> u64 {
+    let service = contract.get_service_by_id(service_id);
+    let deposit = if service.metadata.token == "near" { service.package(package_id).price.0 } else { 0 };
+    set_caller(context, 2, deposit);
+    contract.buy_service(service_id, package_id, None);
+    contract.get_total_orders()
+}
+
+/// Aceptacion de la orden por parte del profesional.
+pub(crate) fn accept(context: &mut VMContextBuilder, contract: &mut Marketplace, order_id: u64) -> Order {
+    set_caller(context, 1, 0);
+    contract.accept_order(order_id)
+}
+
+/// Orden en curso entre el profesional y el empleador.
+pub(crate) fn place_order(context: &mut VMContextBuilder, contract: &mut Marketplace) -> u64 {
+    let service_id = mint(context, contract);
+    let order_id = buy(context, contract, service_id);
+    accept(context, contract, order_id);
+    order_id
+}
+
+/// Mover el reloj al dia indicado, se aplica en la proxima llamada.
+pub(crate) fn at_day(context: &mut VMContextBuilder, days: u64) {
+    context.block_timestamp(ONE_DAY * days);
+}
+
+/// Entrega del trabajo por parte del profesional.
+pub(crate) fn deliver(context: &mut VMContextBuilder, contract: &mut Marketplace, order_id: u64) -> Order {
+    set_caller(context, 1, 0);
+    contract.submit_delivery(order_id, "ipfs://delivery".to_string(), "hash".to_string())
+}
+
+/// Marcar la orden en disputa, como lo hace el callback del mediador.
+pub(crate) fn set_on_dispute(contract: &mut Marketplace, order_id: u64) {
+    let mut order = contract.get_order_by_id(order_id);
+    order.status = OrderStatus::OnDispute;
+    contract.order_by_id.insert(&order_id, &order);
+}
+
+pub(crate) fn near_balance(contract: &Marketplace, index: usize) -> Balance {
+    contract.get_ft_balance_of("near".to_string(), id(index))
+}
+
+pub(crate) fn usdc() -> AccountId {
+    "usdc.near".to_string()
+}
@@ -0,0 +1,106 @@
+use crate::*;
+
+pub(crate) const NEAR_DECIMALS: u8 = 24;
+
+/// Datos de un token soportado, obtenidos de su `ft_metadata`.
+/// Todos los precios y balances se manejan en la unidad minima del token,
+/// por lo que `decimals` solo se usa para mostrarlos.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TokenInfo {
+    pub symbol: String,
+    pub decimals: u8,
+}
+
+/// Campos de la metadata NEP-148 que usa el Marketplace.
+#[derive(Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FtMetadata {
+    pub symbol: String,
+    pub decimals: u8,
+}
+
+#[near_bindgen]
+impl Marketplace {
+    /// Consultar la metadata de un FT para registrarlo como soportado.
+    pub(crate) fn internal_register_token(&self, token: &AccountId) -> Promise {
+        ext_contract::ft_metadata(
+            token, NO_DEPOSIT, BASE_GAS
+        ).then(ext_self::on_add_token(
+            token.clone(),
+            &env::current_account_id(), NO_DEPOSIT, BASE_GAS
+        ))
+    }
+
+    /// #Arguments
+    /// * `token`   - "near" o la cuenta del contrato del token.
+    pub fn get_token_info(&self, token: AccountId) -> TokenInfo {
+        expect_value_found(self.tokens_info.get(&token), b"Token not soported")
+    }
+
+    /// Obtener los tokens soportados junto a su symbol y decimales.
+    pub fn get_tokens_info(&self) -> Vec<(AccountId, TokenInfo)> {
+        self.tokens.iter()
+            .filter_map(|token| self.tokens_info.get(&token).map(|info| (token, info)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn near_and_registered_tokens_keep_their_decimals() {
+        let (_, contract) = setup();
+
+        assert_eq!(contract.get_token_info("near".to_string()).decimals, NEAR_DECIMALS);
+        assert_eq!(contract.get_token_info(usdc()).decimals, 6);
+        assert_eq!(contract.get_tokens_info().len(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "Callback faild")]
+    fn failed_metadata_doesnt_register_the_token() {
+        let (mut context, mut contract) = setup();
+
+        set_callback(&mut context, PromiseResult::Failed);
+        contract.on_add_token("dai.near".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the contract can call its function")]
+    fn only_the_contract_registers_metadata() {
+        let (mut context, mut contract) = setup();
+
+        set_caller(&mut context, 0, 0);
+        contract.on_add_token("dai.near".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Must be owner_id how call its function")]
+    fn only_the_owner_adds_tokens() {
+        let (mut context, mut contract) = setup();
+
+        set_caller(&mut context, 1, 0);
+        contract.add_token(ValidAccountId::try_from("dai.near").unwrap());
+    }
+
+    #[test]
+    #[should_panic(expected = "Token already added")]
+    fn tokens_are_added_once() {
+        let (mut context, mut contract) = setup();
+
+        set_caller(&mut context, 0, 0);
+        contract.add_token(ValidAccountId::try_from("usdc.near").unwrap());
+    }
+
+    #[test]
+    #[should_panic(expected = "Token not soported")]
+    fn services_are_priced_in_supported_tokens() {
+        let (mut context, mut contract) = setup();
+
+        mint_in(&mut context, &mut contract, "dai.near");
+    }
+}
@@ -0,0 +1,222 @@
+use crate::*;
+
+// Comision maxima configurable, 20%.
+pub(crate) const MAX_FEE: u16 = 2000;
+pub(crate) const FEE_PERIOD: u64 = 30 * ONE_DAY;
+
+#[near_bindgen]
+impl Marketplace {
+    /// Descontar la comision de la plataforma de un pago al profesional y acreditarla en la tesoreria,
+    /// salvo la parte que corresponde al referidor de la orden.
+    /// Retorna la comision cobrada.
+    pub(crate) fn internal_collect_fee(&mut self, order_id: u64, token: &AccountId, amount: Balance) -> Balance {
+        let fee = amount * self.get_fee(token.clone()) as u128 / 10_000;
+        if fee == 0 {
+            return 0;
+        }
+        let commission = self.internal_accrue_referral(order_id, token, fee);
+
+        let balance = self.treasury.get(token).unwrap_or(0);
+        self.treasury.insert(token, &(balance + fee - commission));
+
+        let total = self.fee_totals.get(token).unwrap_or(0);
+        self.fee_totals.insert(token, &(total + fee));
+
+        let key = (token.clone(), self.get_fee_period());
+        let period_total = self.fees_by_period.get(&key).unwrap_or(0);
+        self.fees_by_period.insert(&key, &(period_total + fee));
+
+        NearEvent::log_fee_collect(order_id.to_string(), token.clone(), fee.to_string());
+        fee
+    }
+
+
+    /// Modificar la comision de la plataforma, en puntos basicos.
+    /// Sin token se modifica la comision por defecto.
+    ///
+    /// #Arguments
+    /// * `token`   - "near" o la cuenta del contrato del token.
+    /// * `fee`     - Puntos basicos, 100 = 1%.
+    pub fn set_fee(&mut self, token: Option<AccountId>, fee: u16) {
+        self.assert_owner();
+        if fee > MAX_FEE {
+            env::panic(b"Max fee 2000 basis points");
+        }
+        match token {
+            Some(token) => {
+                if !self.tokens.contains(&token) {
+                    env::panic(b"Token not soported");
+                }
+                self.fee_by_token.insert(&token, &fee);
+            }
+            None => self.default_fee = fee,
+        }
+    }
+
+
+    /// Retirar fondos de la tesoreria, acreditandolos en la wallet del Marketplace de quien los recibe.
+    /// Solo ejecutable por el owner o admins, cada retiro queda registrado en un evento.
+    ///
+    /// #Arguments
+    /// * `token`       - "near" o la cuenta del contrato del token.
+    /// * `amount`
+    /// * `receiver_id` - La cuenta de mainnet/testnet que recibe los fondos.
+    /// * `memo`        - Motivo del retiro.
+    pub fn withdraw_treasury(&mut self, token: AccountId, amount: U128, receiver_id: ValidAccountId, memo: String) -> U128 {
+        let sender = env::predecessor_account_id();
+        if sender != self.owner && !self.admins.contains(&sender) {
+            env::panic(b"Only the owner or admins can withdraw from the treasury");
+        }
+        if memo.len() > 180 {
+            env::panic(b"Memo max 180 characters");
+        }
+
+        let balance = self.treasury.get(&token).unwrap_or(0);
+        if amount.0 == 0 || amount.0 > balance {
+            env::panic(b"Insufficient treasury balance");
+        }
+        self.treasury.insert(&token, &(balance - amount.0));
+
+        let receiver_id: AccountId = receiver_id.into();
+        self.internal_deposit(&token, &receiver_id, amount.0);
+
+        NearEvent::log_treasury_withdraw(token, amount.0.to_string(), receiver_id, sender, memo);
+        (balance - amount.0).into()
+    }
+
+
+    /// Comision de la plataforma en puntos basicos para un token.
+    pub fn get_fee(&self, token: AccountId) -> u16 {
+        self.fee_by_token.get(&token).unwrap_or(self.default_fee)
+    }
+
+    /// Balance de la tesoreria de cada token.
+    pub fn get_treasury(&self) -> Vec<(AccountId, U128)> {
+        self.treasury.iter()
+            .map(|(token, balance)| (token, balance.into()))
+            .collect()
+    }
+
+    /// Total de comisiones cobradas de un token desde el inicio.
+    pub fn get_fees_collected(&self, token: AccountId) -> U128 {
+        self.fee_totals.get(&token).unwrap_or(0).into()
+    }
+
+    /// Periodo de 30 dias actual, contado desde el epoch.
+    pub fn get_fee_period(&self) -> u64 {
+        env::block_timestamp() / FEE_PERIOD
+    }
+
+    /// Comisiones cobradas de un token por periodo de 30 dias.
+    ///
+    /// #Arguments
+    /// * `token`       - "near" o la cuenta del contrato del token.
+    /// * `from_period` - Primer periodo a consultar.
+    /// * `limit`       - Cantidad de periodos, hasta 24.
+    pub fn get_fees_by_period(&self, token: AccountId, from_period: u64, limit: u64) -> Vec<(u64, U128)> {
+        (from_period..from_period + std::cmp::min(limit, 24))
+            .map(|period| (period, self.fees_by_period.get(&(token.clone(), period)).unwrap_or(0).into()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+    use crate::test_utils::*;
+
+    fn set_default_fee(context: &mut VMContextBuilder, contract: &mut Marketplace, fee: u16) {
+        set_caller(context, 0, 0);
+        contract.set_fee(None, fee);
+    }
+
+    #[test]
+    fn payments_to_the_professional_collect_the_fee() {
+        let (mut context, mut contract) = setup();
+        set_default_fee(&mut context, &mut contract, 500);
+        let order_id = place_order(&mut context, &mut contract);
+
+        set_caller(&mut context, 2, 0);
+        contract.approve_service(order_id, None, None);
+
+        let fee = PRICE * 500 / 10_000;
+        assert_eq!(near_balance(&contract, 1), PRICE - fee);
+        assert_eq!(contract.get_treasury(), vec![("near".to_string(), U128(fee))]);
+        assert_eq!(contract.get_fees_collected("near".to_string()).0, fee);
+        assert_eq!(contract.get_fees_by_period("near".to_string(), 0, 1), vec![(0, U128(fee))]);
+        assert_eq!(contract.get_escrow_total("near".to_string()).0, 0);
+    }
+
+    #[test]
+    fn refunds_to_the_employer_have_no_fee() {
+        let (mut context, mut contract) = setup();
+        set_default_fee(&mut context, &mut contract, 500);
+        let order_id = place_order(&mut context, &mut contract);
+
+        set_caller(&mut context, 2, 0);
+        contract.cancel_order(order_id);
+
+        assert_eq!(near_balance(&contract, 2), PRICE);
+        assert!(contract.get_treasury().is_empty());
+    }
+
+    #[test]
+    fn token_fees_override_the_default() {
+        let (mut context, mut contract) = setup();
+        set_default_fee(&mut context, &mut contract, 500);
+        contract.set_fee(Some(usdc()), 100);
+
+        assert_eq!(contract.get_fee(usdc()), 100);
+        assert_eq!(contract.get_fee("near".to_string()), 500);
+    }
+
+    #[test]
+    fn treasury_withdrawals_credit_the_receiver_wallet() {
+        let (mut context, mut contract) = setup();
+        set_default_fee(&mut context, &mut contract, 1000);
+        let order_id = place_order(&mut context, &mut contract);
+        set_caller(&mut context, 2, 0);
+        contract.approve_service(order_id, None, None);
+
+        set_caller(&mut context, 0, 0);
+        let left = contract.withdraw_treasury("near".to_string(), U128(600), accounts(3), "Audit".to_string());
+        assert_eq!(left.0, 400);
+        assert_eq!(near_balance(&contract, 3), 600);
+    }
+
+    #[test]
+    #[should_panic(expected = "Insufficient treasury balance")]
+    fn treasury_withdrawals_are_capped_at_the_balance() {
+        let (mut context, mut contract) = setup();
+
+        set_caller(&mut context, 0, 0);
+        contract.withdraw_treasury("near".to_string(), U128(1), accounts(3), "Audit".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner or admins can withdraw from the treasury")]
+    fn only_admins_withdraw_from_the_treasury() {
+        let (mut context, mut contract) = setup();
+
+        set_caller(&mut context, 5, 0);
+        contract.withdraw_treasury("near".to_string(), U128(1), accounts(5), "Audit".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Must be owner_id how call its function")]
+    fn only_the_owner_sets_fees() {
+        let (mut context, mut contract) = setup();
+
+        set_caller(&mut context, 1, 0);
+        contract.set_fee(None, 500);
+    }
+
+    #[test]
+    #[should_panic(expected = "Max fee 2000 basis points")]
+    fn fees_are_capped() {
+        let (mut context, mut contract) = setup();
+
+        set_default_fee(&mut context, &mut contract, MAX_FEE + 1);
+    }
+}
@@ -27,8 +27,12 @@ pub struct PersonalData {
 #[serde(crate = "near_sdk::serde")]
 pub struct User {
     pub account_id: AccountId,
+    // Ultima reputacion calculada sobre 50 y cantidad de puntajes recibidos.
     pub reputation: u16,
     pub votes: u16,
+    // Reputacion como empleador, segun los puntajes de los profesionales de sus ordenes.
+    pub employer_reputation: u16,
+    pub employer_votes: u16,
     pub is_employee: bool,
     pub is_company: bool,
     pub personal_data: Option<String>,
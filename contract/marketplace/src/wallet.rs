@@ -0,0 +1,199 @@
+use crate::*;
+
+#[near_bindgen]
+impl Marketplace {
+    /// Acreditar tokens en el balance interno de un usuario.
+    pub(crate) fn internal_deposit(&mut self, token: &AccountId, account_id: &AccountId, amount: Balance) {
+        let key = (token.clone(), account_id.clone());
+        let balance = self.balances.get(&key).unwrap_or(0);
+        self.balances.insert(&key, &(balance + amount));
+
+        let total = self.balance_totals.get(token).unwrap_or(0);
+        self.balance_totals.insert(token, &(total + amount));
+    }
+
+    /// Acreditar el NEAR adjunto a la llamada en el balance interno de un usuario.
+    pub(crate) fn internal_deposit_attached(&mut self, account_id: &AccountId) {
+        let amount = env::attached_deposit();
+        if amount > 0 {
+            self.internal_deposit(&"near".to_string(), account_id, amount);
+        }
+    }
+
+    /// Debitar tokens del balance interno de un usuario.
+    pub(crate) fn internal_withdraw(&mut self, token: &AccountId, account_id: &AccountId, amount: Balance) -> Balance {
+        let key = (token.clone(), account_id.clone());
+        let balance = self.balances.get(&key).unwrap_or(0);
+        if balance < amount {
+            env::panic(format!("Insufficient {} balance in the marketplace wallet", token).as_bytes());
+        }
+        let new_balance = balance - amount;
+        if new_balance == 0 {
+            self.balances.remove(&key);
+        } else {
+            self.balances.insert(&key, &new_balance);
+        }
+
+        let total = self.balance_totals.get(token).unwrap_or(0);
+        self.balance_totals.insert(token, &(total - amount));
+        new_balance
+    }
+
+
+    /// Balance de un token en la wallet del Marketplace de quien llama.
+    pub fn get_ft_balance(&self, token: AccountId) -> Balance {
+        self.get_ft_balance_of(token, env::predecessor_account_id())
+    }
+
+    /// Balance de un token en la wallet del Marketplace de un usuario.
+    ///
+    /// #Arguments
+    /// * `token`   - La cuenta del contrato del token.
+    /// * `user`    - La cuenta de mainnet/testnet del usuario.
+    pub fn get_ft_balance_of(&self, token: AccountId, user: AccountId) -> Balance {
+        self.balances.get(&(token, user)).unwrap_or(0)
+    }
+
+    /// Todos los balances distintos de cero de un usuario en la wallet del Marketplace.
+    ///
+    /// #Arguments
+    /// * `account_id`  - La cuenta de mainnet/testnet del usuario.
+    pub fn get_balances_of(&self, account_id: ValidAccountId) -> Vec<(AccountId, U128)> {
+        let account_id: AccountId = account_id.into();
+        self.tokens.iter()
+            .filter_map(|token| {
+                self.balances.get(&(token.clone(), account_id.clone()))
+                    .map(|balance| (token, balance.into()))
+            })
+            .collect()
+    }
+
+    /// Total depositado por los usuarios de un token, sin contar lo bloqueado en escrow.
+    pub fn get_balance_total(&self, token: AccountId) -> U128 {
+        self.balance_totals.get(&token).unwrap_or(0).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn ft_orders_are_paid_from_the_wallet() {
+        let (mut context, mut contract) = setup();
+        contract.internal_deposit(&usdc(), &id(2), 2 * PRICE);
+        let service_id = mint_in(&mut context, &mut contract, "usdc.near");
+        let order_id = buy(&mut context, &mut contract, service_id);
+
+        assert_eq!(contract.get_ft_balance_of(usdc(), id(2)), PRICE);
+        assert_eq!(contract.get_balance_total(usdc()).0, PRICE);
+        assert_eq!(contract.get_escrow(order_id).token, usdc());
+        assert_eq!(contract.get_escrow_total(usdc()).0, PRICE);
+    }
+
+    #[test]
+    fn approve_credits_the_professional_wallet() {
+        let (mut context, mut contract) = setup();
+        contract.internal_deposit(&usdc(), &id(2), PRICE);
+        let service_id = mint_in(&mut context, &mut contract, "usdc.near");
+        let order_id = buy(&mut context, &mut contract, service_id);
+        accept(&mut context, &mut contract, order_id);
+
+        set_caller(&mut context, 2, 0);
+        contract.approve_service(order_id, None, None);
+
+        assert_eq!(contract.get_ft_balance_of(usdc(), id(1)), PRICE);
+        assert_eq!(contract.get_ft_balance_of(usdc(), id(2)), 0);
+        assert_eq!(contract.get_balances_of(accounts(1)), vec![(usdc(), U128(PRICE))]);
+        assert_eq!(contract.get_escrow_total(usdc()).0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Insufficient usdc.near balance in the marketplace wallet")]
+    fn ft_orders_require_the_full_price_in_the_wallet() {
+        let (mut context, mut contract) = setup();
+        contract.internal_deposit(&usdc(), &id(2), PRICE - 1);
+        let service_id = mint_in(&mut context, &mut contract, "usdc.near");
+        buy(&mut context, &mut contract, service_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Token not soported")]
+    fn withdraw_ft_rejects_near() {
+        let (mut context, mut contract) = setup();
+
+        set_caller(&mut context, 2, 0);
+        contract.withdraw_ft(U128(1), "near".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Insufficient usdc.near balance in the marketplace wallet")]
+    fn withdraw_ft_cant_exceed_the_balance() {
+        let (mut context, mut contract) = setup();
+        contract.internal_deposit(&usdc(), &id(2), PRICE);
+
+        set_caller(&mut context, 2, 0);
+        contract.withdraw_ft(U128(PRICE + 1), usdc());
+    }
+
+    #[test]
+    fn near_attached_over_the_price_stays_in_the_wallet() {
+        let (mut context, mut contract) = setup();
+        let service_id = mint(&mut context, &mut contract);
+
+        set_caller(&mut context, 2, 3 * PRICE);
+        contract.buy_service(service_id, 0, None);
+        assert_eq!(near_balance(&contract, 2), 2 * PRICE);
+
+        // La siguiente compra se paga con lo que quedo en la wallet.
+        set_caller(&mut context, 2, 0);
+        contract.buy_service(service_id, 0, None);
+        assert_eq!(near_balance(&contract, 2), PRICE);
+        assert_eq!(contract.get_escrow_total("near".to_string()).0, 2 * PRICE);
+    }
+
+    #[test]
+    fn near_payments_are_withdrawn_from_the_wallet() {
+        let (mut context, mut contract) = setup();
+        let order_id = place_order(&mut context, &mut contract);
+
+        set_caller(&mut context, 2, 0);
+        contract.approve_service(order_id, None, None);
+        assert_eq!(near_balance(&contract, 1), PRICE);
+
+        set_caller(&mut context, 1, 0);
+        assert_eq!(contract.withdraw_near(U128(PRICE / 2)), PRICE / 2);
+        assert_eq!(contract.get_balance_total("near".to_string()).0, PRICE / 2);
+    }
+
+    #[test]
+    fn deposits_can_credit_another_account() {
+        let (mut context, mut contract) = setup();
+
+        set_caller(&mut context, 2, PRICE);
+        assert_eq!(contract.deposit_near(Some(accounts(3))).0, PRICE);
+        assert_eq!(near_balance(&contract, 2), 0);
+        assert_eq!(near_balance(&contract, 3), PRICE);
+    }
+
+    #[test]
+    #[should_panic(expected = "Insufficient near balance in the marketplace wallet")]
+    fn withdraw_near_cant_exceed_the_balance() {
+        let (mut context, mut contract) = setup();
+
+        set_caller(&mut context, 2, PRICE);
+        contract.deposit_near(None);
+        set_caller(&mut context, 2, 0);
+        contract.withdraw_near(U128(PRICE + 1));
+    }
+
+    #[test]
+    #[should_panic(expected = "Attach the NEAR to deposit")]
+    fn deposits_require_attached_near() {
+        let (mut context, mut contract) = setup();
+
+        set_caller(&mut context, 2, 0);
+        contract.deposit_near(None);
+    }
+}
@@ -22,7 +22,8 @@ pub enum Event {
     DisputeNew(DisputeNewData),
     DisputeApplication(DisputeApplicationData),
     DisputeVote(DisputeVoteData),
-    DisputeChangeStatus(DisputeChangeStatusData)
+    DisputeChangeStatus(DisputeChangeStatusData),
+    DisputeRollback(DisputeRollbackData)
 }
 
 
@@ -30,7 +31,7 @@ pub enum Event {
 #[derive(Serialize, Deserialize, Debug)]
 pub struct DisputeNewData {
     id: u64,
-    service_id: u64, 
+    order_id: u64, 
     applicant: String, 
     accused: String, 
     jury_members: Vec<String>,
@@ -53,6 +54,9 @@ pub struct DisputeVoteData {id: u64, account_id: String, vote: bool}
 #[derive(Serialize, Deserialize, Debug)]
 pub struct DisputeChangeStatusData {id: u64, status: String}
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DisputeRollbackData {id: u64, order_id: u64, winner: String, price: u128}
+
 
 impl Display for Event {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -64,7 +68,7 @@ impl Event {
     // Creacion de una disputa.
     pub fn log_dispute_new(
         id: u64,
-        service_id: u64, 
+        order_id: u64, 
         applicant: String, 
         accused: String, 
         jury_members: Vec<String>,
@@ -77,7 +81,7 @@ impl Event {
         price: u128,
         winner: Option<String>
     ) {
-        let data = DisputeNewData { id, service_id, applicant, accused, jury_members,
+        let data = DisputeNewData { id, order_id, applicant, accused, jury_members,
             votes, dispute_status, initial_timestamp, finish_timestamp, 
             applicant_proves, accused_proves, price, winner,
         };
@@ -103,6 +107,12 @@ impl Event {
         Event::DisputeChangeStatus(data).log();
     }
 
+    // Reapertura de una disputa cuyo cierre no pudo liberarse en el Marketplace.
+    pub fn log_dispute_rollback(id: u64, order_id: u64, winner: String, price: u128) {
+        let data = DisputeRollbackData { id, order_id, winner, price };
+        Event::DisputeRollback(data).log();
+    }
+
 
     // Funciones internas.
     fn log(&self) { near_sdk::env::log(&self.to_string().as_bytes()); }
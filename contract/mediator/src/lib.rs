@@ -4,13 +4,14 @@ use near_sdk::{ env, ext_contract, near_bindgen, setup_alloc, AccountId, Balance
 // , Promise, serde_json::{json}};
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::collections::UnorderedMap;
-use near_sdk::json_types::{ValidAccountId, U128};
+use near_sdk::json_types::U128;
 use near_sdk::serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 use std::fmt::{Debug, Display, Formatter, Result};
 // use std::convert::TryFrom;
 
 mod events;
+mod migration;
 use events::Event;
 
 // const YOCTO_NEAR: u128 = 1000000000000000000000000;
@@ -18,10 +19,10 @@ use events::Event;
 // const NANO_SECONDS: u32 = 1_000_000_000;
 const NO_DEPOSIT: Balance = 0;
 const BASE_GAS: Gas = 30_000_000_000_000;
-const MAX_GAS: Gas = 250_000_000_000_000;
+// Callback de la liberacion en Marketplace: su propia ejecucion mas la llamada de premios al jurado.
+const GAS_ON_RETURN_SERVICE: Gas = 2 * BASE_GAS;
 const ONE_DAY: u64 = 86400000000000;
 const YOCTO_NEAR: u128 = 1000000000000000000000000;
-const GAS_FT_TRANSFER: Gas = 14_000_000_000_000;
 
 setup_alloc!();
 
@@ -61,7 +62,8 @@ pub struct Vote {
 pub struct Dispute {
     // Identificador para cada disputa.
     id: DisputeId,
-    service_id: u64,
+    // Orden del Marketplace en disputa.
+    order_id: u64,
     // Lista de miembros del jurado y sus respectivos votos.
     jury_members: Vec<AccountId>,
     votes: HashSet<Vote>,
@@ -77,8 +79,9 @@ pub struct Dispute {
     // Proofs.
     applicant_proves: String,       // Un markdown con las pruebas.
     accused_proves: Option<String>, // Un markdown con las pruebas.
-    // Precio pagado por el servicio.
-    price: u128,
+    // Monto en escrow en el Marketplace que se resuelve con la disputa,
+    // en la unidad minima del token de la orden.
+    price: U128,
 }
 
 #[near_bindgen]
@@ -120,13 +123,13 @@ impl Mediator {
     /// Ejecutable desde Marketplace por el empleador que haya comprado el servicio.
     /// 
     #[payable]
-    pub fn new_dispute(&mut self, service_id: u64, applicant: AccountId, accused: AccountId, proves: String, price: u128) -> u64 {
+    pub fn new_dispute(&mut self, order_id: u64, applicant: AccountId, accused: AccountId, proves: String, price: U128) -> u64 {
         if env::attached_deposit() < 1 {
             env::panic(b"To create a new dispute, deposit 0.1 near");
         }
         let dispute = Dispute {
             id: self.disputes_counter.clone(),
-            service_id: service_id,
+            order_id: order_id,
             jury_members: Vec::new(),
             votes: HashSet::new(),
             dispute_status: DisputeStatus::Open,
@@ -148,7 +151,7 @@ impl Mediator {
 
         Event::log_dispute_new(
             dispute.id.clone(),
-            dispute.service_id.clone(),
+            dispute.order_id.clone(),
             dispute.applicant.clone(),
             dispute.accused.clone(),
             dispute.jury_members.clone(),
@@ -158,7 +161,7 @@ impl Mediator {
             0,
             dispute.applicant_proves.clone(),
             "".to_string(),
-            dispute.price.clone(),
+            dispute.price.0,
             None
         );
 
@@ -345,46 +348,6 @@ impl Mediator {
     }
 
 
-    /// Pagar al profesional o empleador según corresponda.
-    /// Solo ejecutable desde Marketplace.
-    /// 
-    pub fn pay_service(&self, beneficiary: AccountId, amount: U128, token: String) {
-        let sender = env::predecessor_account_id();
-        env::log(sender.as_bytes());
-        env::log(self.marketplace_contract.as_bytes());
-        env::log(self.owner.as_bytes());
-        if sender != self.marketplace_contract && sender != self.owner {
-            env::panic(b"You don't have permissions to generate a payment");
-        }
-
-        if token == "near".to_string() {
-            // Realizar el pago en NEARs.
-            Promise::new(beneficiary).transfer(amount.0 * YOCTO_NEAR);
-        } else {
-            ext_contract::ft_transfer(
-                beneficiary.clone(),
-                amount.clone(),
-                None,
-                &token, 
-                1, 
-                GAS_FT_TRANSFER
-            );
-        }
-    }
-
-    /// Pagar al profesional o empleador según corresponda.
-    /// Solo ejecutable internamente por Mediator.
-    /// 
-    pub fn internal_pay_service(&self, beneficiary: AccountId, amount: Balance) -> Balance {
-        self.assert_owner(&env::signer_account_id());
-
-        // Realizar el pago en NEARs.
-        Promise::new(beneficiary).transfer(amount);
-
-        env::account_balance()
-    }
-
-
     /// Para verificar y actualizar el estado de la disputa.
     ///
     #[payable]
@@ -428,37 +391,23 @@ impl Mediator {
                 dispute.dispute_status = DisputeStatus::Finished;
                 if pro_votes_counter > agains_votes_counter {
                     dispute.winner = Some(dispute.applicant.clone());
-
-                    // Pagar al empleador
-                    Promise::new(dispute.applicant.clone()).transfer(dispute.price.clone());
-
-                    let _res = ext_ft::applicant_winner(
-                        dispute.votes.clone(),
-                        &self.token_contract,
-                        NO_DEPOSIT, MAX_GAS
-                    );
                 }
                 else {
                     dispute.winner = Some(dispute.accused.clone());
-
-                    // Pagar al profesional
-                    Promise::new(dispute.accused.clone()).transfer(dispute.price.clone());
-
-                    let _res = ext_ft::accused_winner(
-                        dispute.votes.clone(),
-                        &self.token_contract,
-                        NO_DEPOSIT, MAX_GAS
-                    );
                 }
 
                 dispute.finish_timestamp = Some(env::block_timestamp());
 
+                // El Marketplace libera el escrow de la orden al ganador.
+                // El jurado se premia recien cuando se confirma la liberacion.
+                let applicant_winner = dispute.winner == Some(dispute.applicant.clone());
                 let _res = ext_marketplace::return_service_by_mediator(
-                    dispute.service_id,
+                    dispute.order_id,
+                    applicant_winner,
                     &self.marketplace_contract, NO_DEPOSIT, BASE_GAS)
                 .then(ext_self::on_return_service(
-                    dispute.service_id,
-                    &env::current_account_id(), NO_DEPOSIT, BASE_GAS)
+                    dispute.id.clone(),
+                    &env::current_account_id(), NO_DEPOSIT, GAS_ON_RETURN_SERVICE)
                 );
             }
         }
@@ -599,16 +548,18 @@ impl Mediator {
     }
 
     // Verificacion de que es un Admin.
-    fn assert_owner(&self, account: &AccountId) {
+    fn assert_admin(&self, account: &AccountId) {
         if !self.admins.contains(&account) {
             env::panic(b"Isn't an Admin");
         }
     }
     
 
-    /// Callback para retornar el servicio al profesional en Marketplace.
+    /// Callback luego de cerrarse la orden en Marketplace.
+    /// Si se libero el escrow se premia al jurado, si no la disputa vuelve a quedar ejecutable
+    /// para reintentar su cierre.
     /// 
-    pub fn on_return_service(_service_id: u64) {
+    pub fn on_return_service(&mut self, dispute_id: DisputeId) -> bool {
         if env::predecessor_account_id() != env::current_account_id() {
             env::panic(b"only the contract can call its function")
         }
@@ -616,13 +567,46 @@ impl Mediator {
             env::promise_results_count(), 1,
             "Contract expected a result on the callback"
         );
+        let mut dispute = self.get_dispute(dispute_id.clone());
         match env::promise_result(0) {
             PromiseResult::Successful(_data) => {
-                env::log(b"Service returned to creator");
+                env::log(b"Order closed in marketplace");
+
+                if dispute.winner == Some(dispute.applicant.clone()) {
+                    let _res = ext_ft::applicant_winner(
+                        dispute.votes.clone(),
+                        &self.token_contract,
+                        NO_DEPOSIT, BASE_GAS
+                    );
+                }
+                else {
+                    let _res = ext_ft::accused_winner(
+                        dispute.votes.clone(),
+                        &self.token_contract,
+                        NO_DEPOSIT, BASE_GAS
+                    );
+                }
+                true
             },
-            PromiseResult::Failed => env::panic(b"Callback faild"),
-            PromiseResult::NotReady => env::panic(b"Callback faild"),
-        };
+            _ => {
+                let winner = dispute.winner.take().unwrap_or_default();
+                dispute.dispute_status = DisputeStatus::Executable;
+                dispute.finish_timestamp = None;
+                self.disputes.insert(&dispute_id, &dispute);
+
+                Event::log_dispute_rollback(
+                    dispute_id,
+                    dispute.order_id.clone(),
+                    winner,
+                    dispute.price.0
+                );
+                Event::log_dispute_change_status(
+                    dispute_id,
+                    dispute.dispute_status.to_string()
+                );
+                false
+            }
+        }
     }
 
     /// Callback para incrementar en 3% los tokens de quien voto correctamente.
@@ -676,7 +660,7 @@ impl Mediator {
 #[ext_contract(ext_marketplace)]
 pub trait Marketplace {
     fn validate_user(account_id: AccountId);
-    fn return_service_by_mediator(service_id: u64);
+    fn return_service_by_mediator(order_id: u64, applicant_winner: bool);
     fn ban_user_by_mediator(user_id: AccountId);
 }
 #[ext_contract(ext_ft)]
@@ -689,13 +673,12 @@ pub trait ExtFT {
 pub trait ExtSelf {
     fn on_pre_vote(dispute_id: u64, user_id: AccountId);
     fn on_vote(dispute_id: u64, user_id: AccountId, vote: bool);
-    fn on_return_service(service_id: u64);
+    fn on_return_service(dispute_id: DisputeId);
     fn on_ban_user();
 }
-#[ext_contract(ext_contract)]
-trait ExtContract {
-    fn ft_transfer(&mut self, receiver_id: AccountId, amount: U128, memo: Option<String>);
-    fn ft_transfer_call(&mut self, receiver_id: ValidAccountId, amount: U128, memo: Option<String>, msg: String) -> PromiseOrValue<U128>;
+#[ext_contract(ext_token)]
+pub trait ExtToken {
+    fn ft_transfer(receiver_id: AccountId, amount: U128, memo: Option<String>);
 }
 
 fn expect_value_found<T>(option: Option<T>, message: &[u8]) -> T {
@@ -0,0 +1,114 @@
+use crate::*;
+
+/// Disputa con el formato anterior, sobre un servicio cuyo pago retenia el Mediator.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct OldDispute {
+    id: DisputeId,
+    service_id: u64,
+    jury_members: Vec<AccountId>,
+    votes: HashSet<Vote>,
+    dispute_status: DisputeStatus,
+    initial_timestamp: u64,
+    finish_timestamp: Option<u64>,
+    applicant: AccountId,
+    accused: AccountId,
+    winner: Option<AccountId>,
+    applicant_proves: String,
+    accused_proves: Option<String>,
+    price: u128,
+}
+
+/// Estado del contrato anterior a la migracion.
+#[derive(BorshDeserialize)]
+pub struct OldMediator {
+    disputes: UnorderedMap<DisputeId, OldDispute>,
+    disputes_counter: u64,
+    owner: AccountId,
+    admins: Vec<AccountId>,
+    marketplace_contract: AccountId,
+    token_contract: AccountId,
+    max_jurors: u8,
+}
+
+#[near_bindgen]
+impl Mediator {
+    /// Migrar las disputas del formato anterior, que referenciaban el servicio comprado, al actual por orden.
+    /// Se ejecuta luego de migrar el Marketplace, con la orden que este creo para cada servicio vendido.
+    /// Solo ejecutable por el owner.
+    ///
+    /// #Arguments
+    /// * `orders`  - Pares (servicio, orden). Toda disputa sin terminar tiene que tener su orden.
+    #[init(ignore_state)]
+    pub fn migrate(orders: Vec<(u64, u64)>) -> Self {
+        let mut old: OldMediator = expect_value_found(env::state_read(), b"The contract isn't initialized");
+        if env::predecessor_account_id() != old.owner {
+            env::panic(b"Only the owner can migrate the contract");
+        }
+        let order_by_service: std::collections::HashMap<u64, u64> = orders.into_iter().collect();
+
+        // Se reescriben bajo el mismo prefijo, por eso primero se vacia el mapa anterior.
+        let old_disputes = old.disputes.to_vec();
+        old.disputes.clear();
+        let mut disputes: UnorderedMap<DisputeId, Dispute> = UnorderedMap::new(b"d");
+        for (id, old_dispute) in old_disputes {
+            // Las disputas terminadas conservan el id del servicio, ya no se liberan en el Marketplace.
+            let order_id = match order_by_service.get(&old_dispute.service_id) {
+                Some(order_id) => *order_id,
+                None if old_dispute.dispute_status == DisputeStatus::Finished => old_dispute.service_id,
+                None => env::panic(format!("Missing the order of the service {}", old_dispute.service_id).as_bytes()),
+            };
+            disputes.insert(&id, &Dispute {
+                id: old_dispute.id,
+                order_id: order_id,
+                jury_members: old_dispute.jury_members,
+                votes: old_dispute.votes,
+                dispute_status: old_dispute.dispute_status,
+                initial_timestamp: old_dispute.initial_timestamp,
+                finish_timestamp: old_dispute.finish_timestamp,
+                applicant: old_dispute.applicant,
+                accused: old_dispute.accused,
+                winner: old_dispute.winner,
+                applicant_proves: old_dispute.applicant_proves,
+                accused_proves: old_dispute.accused_proves,
+                price: old_dispute.price.into(),
+            });
+        }
+
+        Self {
+            disputes: disputes,
+            disputes_counter: old.disputes_counter,
+            owner: old.owner,
+            admins: old.admins,
+            marketplace_contract: old.marketplace_contract,
+            token_contract: old.token_contract,
+            max_jurors: old.max_jurors,
+        }
+    }
+
+
+    /// Enviar al Marketplace los pagos de servicios vendidos antes de la migracion, que retenia el Mediator.
+    /// El Marketplace los tiene en escrow en las ordenes que creo para esos servicios.
+    /// Solo ejecutable por el owner, para FT se adjunta 1 yocto.
+    ///
+    /// #Arguments
+    /// * `token`   - "near" o la cuenta del contrato del token.
+    /// * `amount`  - En la unidad minima del token.
+    #[payable]
+    pub fn forward_legacy_funds(&mut self, token: AccountId, amount: U128) -> Promise {
+        if env::predecessor_account_id() != self.owner {
+            env::panic(b"Only the owner can forward the legacy funds");
+        }
+        if token == "near" {
+            return Promise::new(self.marketplace_contract.clone()).transfer(amount.0);
+        }
+        if env::attached_deposit() != 1 {
+            env::panic(b"Requires attached deposit of exactly 1 yoctoNEAR");
+        }
+        ext_token::ft_transfer(
+            self.marketplace_contract.clone(),
+            amount,
+            Some("Legacy sales".to_string()),
+            &token, 1, BASE_GAS
+        )
+    }
+}
@@ -18,15 +18,16 @@ import {
   Timestamp,
 } from "firebase/firestore";
 
-export default function Chat({ service }) {
+// Sala de chat entre el profesional y el comprador de una orden.
+export default function Chat({ order }) {
   let salasRef = doc(
     fireStore,
     "salas",
-    `${service.id}&${service.creator_id}&${service.actual_owner}`
+    `${order.service_id}&${order.creator_id}&${order.buyer_id}`
   );
   let messagesRef = collection(
     fireStore,
-    `salas/${service.id}&${service.creator_id}&${service.actual_owner}/messages`
+    `salas/${order.service_id}&${order.creator_id}&${order.buyer_id}/messages`
   );
 
   let messagesQuery = query(messagesRef, orderBy("createdAt"), limit(25));
@@ -39,14 +40,14 @@ export default function Chat({ service }) {
   const submitRef = useRef()
 
   useEffect(async () => {
-    console.log(service);
+    console.log(order);
     console.log(messages);
     singInFirebaseAnonymously();
 
     const salasDoc = await getDoc(salasRef);
     if (!salasDoc.exists()) {
       await setDoc(salasRef, {
-        members: [service.creator_id, service.actual_owner],
+        members: [order.creator_id, order.buyer_id],
       });
     }
 
@@ -57,15 +58,15 @@ export default function Chat({ service }) {
     salasRef = doc(
       fireStore,
       "salas",
-      `${service.id}&${service.creator_id}&${service.actual_owner}`
+      `${order.service_id}&${order.creator_id}&${order.buyer_id}`
     );
     messagesRef = collection(
       fireStore,
-      `salas/${service.id}&${service.creator_id}&${service.actual_owner}/messages`
+      `salas/${order.service_id}&${order.creator_id}&${order.buyer_id}/messages`
     );
 
     messagesQuery = query(messagesRef, orderBy("createdAt"), limit(25));
-  }, [service]);
+  }, [order]);
 
   const handleOnChange = (e) => {
     setNewMessage(e.target.value);
@@ -10,7 +10,7 @@ export default function CreateDisputeDialog({
   isOpen,
   closeModal,
   openModal,
-  serviceId,
+  orderId,
   disputeId,
 }) {
   const [typing, setTyping] = useState(false);
@@ -111,7 +111,7 @@ export default function CreateDisputeDialog({
                     if (proveInput.length > 0) {
                       if (disputeId == null) {
                         setSendinigProves(true);
-                        await reclaimDispute(serviceId, proveInput);
+                        await reclaimDispute(orderId, proveInput);
                         setSendinigProves(false);
                       } else {
                         await addAccusedProves(disputeId, proveInput);
@@ -10,7 +10,13 @@ import tokensData from "../tokensData.json";
 
 // import makeAnimated from 'react-select/animated';
 
-import { mintService, updateService } from "../utils";
+import {
+  fromBaseUnits,
+  getTokenInfo,
+  mintService,
+  toBaseUnits,
+  updateService,
+} from "../utils";
 
 export default function CreateServiceDialog({
   isOpen,
@@ -33,9 +39,7 @@ export default function CreateServiceDialog({
       : []
   );
   const [iconServiceFile, setIconServiceFile] = useState(null);
-  const [priceService, setPriceService] = useState(
-    service ? service.metadata.price : 0
-  );
+  const [priceService, setPriceService] = useState(0);
   const [durationService, setDurationService] = useState(
     service ? service.duration : 0
   );
@@ -51,6 +55,19 @@ export default function CreateServiceDialog({
         }
   );
 
+  // El precio guardado esta en la unidad minima del token, se edita en unidades del token.
+  useEffect(() => {
+    if (service) {
+      getTokenInfo(service.metadata.token).then((tokenInfo) => {
+        if (tokenInfo) {
+          setPriceService(
+            Number(fromBaseUnits(service.metadata.price, tokenInfo.decimals))
+          );
+        }
+      });
+    }
+  }, [service]);
+
   const filterCategories = (inputValue) => {
     return categoriesData.filter((i) =>
       i.label.toLowerCase().includes(inputValue.toLowerCase())
@@ -458,11 +475,22 @@ export default function CreateServiceDialog({
                           ];
 
                       let amt = utils.format.parseNearAmount("0.1");
+                      const tokenInfo = paidmentMethod.value
+                        ? await getTokenInfo(paidmentMethod.value.toLowerCase())
+                        : null;
+                      if (paidmentMethod.value && !tokenInfo) {
+                        validateInputs.push({
+                          ok: false,
+                          msg: "Payment's method isn't supported",
+                        });
+                      }
                       let serviceMetadata = {
                         title: titleService,
                         description: descriptionService,
                         icon: service ? service.metadata.icon : "",
-                        price: priceService,
+                        price: tokenInfo
+                          ? toBaseUnits(priceService, tokenInfo.decimals)
+                          : "0",
                         categories: JSON.stringify(
                           categoriesService.map((v) => v.value)
                         ),
@@ -504,7 +532,6 @@ export default function CreateServiceDialog({
                           if (!service) {
                             await mintService(
                               serviceMetadata,
-                              amountOfServices,
                               durationService,
                               amt
                             );
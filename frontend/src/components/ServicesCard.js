@@ -1,12 +1,29 @@
-import React from "react";
+import React, { useEffect, useState } from "react";
 
 import { ImCross, ImCheckmark } from "react-icons/im";
 import { useNavigate } from "react-router-dom";
 
 import {TokenIcons} from "./TokenIcons";
+import { fromBaseUnits, getTokenInfo } from "../utils";
 
-export default function ServicesCard({ service }) {
+// Si se pasa una orden, se muestra su estado y el rol del usuario en ella.
+export default function ServicesCard({ service, order }) {
   const navigate = useNavigate();
+  const [decimals, setDecimals] = useState(null);
+
+  useEffect(() => {
+    getTokenInfo(service.metadata.token).then((tokenInfo) => {
+      if (tokenInfo) {
+        setDecimals(tokenInfo.decimals);
+      }
+    });
+  }, [service.metadata.token]);
+
+  // Los precios vienen en la unidad minima del token.
+  const formatPrice = (amount) => {
+    return decimals != null ? fromBaseUnits(amount, decimals) : "...";
+  };
+
   return (
     <div
       onClick={() => {
@@ -42,10 +59,14 @@ export default function ServicesCard({ service }) {
             <span className="font-semibold">Creator: </span>
             {service.creator_id}
           </div>
-          <div className="mr-2">
-            <span className="font-semibold">Owner: </span>
-            {service.actual_owner}
-          </div>
+          {order ? (
+            <div className="mr-2">
+              <span className="font-semibold">Buyer: </span>
+              {order.buyer_id}
+            </div>
+          ) : (
+            <></>
+          )}
         </div>
         <div className="flex items-center whitespace-pre-wrap">
           <div className="mr-2 font-semibold">
@@ -53,17 +74,6 @@ export default function ServicesCard({ service }) {
             <span className="font-light"> {service.duration} Days</span>
           </div>
 
-          <div className="mr-2 flex items-center font-semibold">
-            Sold:
-            <div className=" font-light mx-1">
-              {service.sold ? (
-                <ImCheckmark color="green" />
-              ) : (
-                <ImCross color="red" />
-              )}
-            </div>
-          </div>
-
           <div className="flex items-center mr-2 font-semibold">
             On Sale:
             <div className="font-light mx-1">
@@ -75,21 +85,47 @@ export default function ServicesCard({ service }) {
             </div>
           </div>
 
-          <div className="flex items-center mr-2 font-semibold">
-            On Dispute:
-            <div className="font-light mx-1">
-              {service.on_dispute ? (
-                <ImCheckmark color="green" />
-              ) : (
-                <ImCross color="red" />
-              )}
+          {order ? (
+            <div className="mr-2 font-semibold">
+              Order:
+              <span className="font-light">
+                {" "}
+                {order.status} as{" "}
+                {order.buyer_id == window.accountId ? "buyer" : "professional"}
+              </span>
             </div>
-          </div>
+          ) : (
+            <></>
+          )}
         </div>
       </div>
+      {service.metadata.packages && service.metadata.packages.length > 0 ? (
+        <div className="flex flex-row mt-4">
+          {service.metadata.packages.map((p, i) => {
+            return (
+              <div
+                key={i}
+                className="mr-2 p-2 rounded-md border border-[#27C0EF] text-sm"
+              >
+                <div className="text-[#034D82]">{p.name}</div>
+                <div className="font-light">
+                  {formatPrice(p.price)} - {p.duration} Days - {p.revisions} Revisions
+                </div>
+                <ul className="font-light text-xs list-disc list-inside">
+                  {p.features.map((f, j) => {
+                    return <li key={j}>{f}</li>;
+                  })}
+                </ul>
+              </div>
+            );
+          })}
+        </div>
+      ) : (
+        <></>
+      )}
       <div className="flex justify-between mt-4">
         <div className=" text-sm flex items-center">
-          {service.metadata.price}
+          {formatPrice(service.metadata.price)}
           <img
             className="w-[26px] ml-1"
             src={
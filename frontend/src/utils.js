@@ -58,6 +58,7 @@ export async function initContract() {
         "get_user_service_id",
         "get_total_services",
         "get_services",
+        "get_user_orders",
         "get_ft_balance",
         "get_ft_balance_of",
       ],
@@ -174,19 +175,46 @@ function getErrMsg(e) {
   return finalErrorMsg
 }
 
+/* Tokens relate */
+
+// Los precios se guardan en la unidad minima de cada token, segun sus decimales.
+const tokensInfo = {};
+
+export async function getTokenInfo(token) {
+  if (!tokensInfo[token]) {
+    tokensInfo[token] = window.marketplaceContract
+      .get_token_info({ token: token })
+      .catch((e) => {
+        delete tokensInfo[token];
+        console.log(e);
+        return null;
+      });
+  }
+  return await tokensInfo[token];
+}
+
+// "1.5" con 6 decimales -> "1500000"
+export function toBaseUnits(amount, decimals) {
+  let [whole, fraction = ""] = String(amount).split(".");
+  fraction = fraction.padEnd(decimals, "0").slice(0, decimals);
+  return (whole + fraction).replace(/^0+(?=\d)/, "");
+}
+
+// "1500000" con 6 decimales -> "1.5"
+export function fromBaseUnits(amount, decimals) {
+  let digits = String(amount).padStart(decimals + 1, "0");
+  let whole = digits.slice(0, digits.length - decimals);
+  let fraction = digits.slice(digits.length - decimals).replace(/0+$/, "");
+  return fraction ? `${whole}.${fraction}` : whole;
+}
+
 /* Services relate */
 
-export async function mintService(
-  serviceMetadata,
-  amountOfServices,
-  durationService,
-  amt
-) {
+export async function mintService(serviceMetadata, durationService, amt) {
   try {
     return await window.marketplaceContract.mint_service(
       {
         metadata: serviceMetadata,
-        quantity: amountOfServices,
         duration: durationService,
       },
       "300000000000000",
@@ -225,10 +253,10 @@ export async function withdrawFT(amount, token) {
   }
 }
 
-export async function buyService(serviceId, deposit) {
+export async function buyService(serviceId, deposit, packageId = 0) {
   try {
     await window.marketplaceContract.buy_service(
-      { service_id: serviceId },
+      { service_id: serviceId, package_id: packageId },
       "300000000000000",
       deposit
     );
@@ -266,11 +294,11 @@ export async function updateService(
   }
 }
 
-export async function reclaimService(serviceId) {
+export async function reclaimService(orderId) {
   // let fee = utils.format.parseNearAmount("0.1");
   try {
     await window.marketplaceContract.reclaim_service(
-      { service_id: serviceId },
+      { order_id: orderId },
       "300000000000000"
     );
     return true;
@@ -307,6 +335,19 @@ export async function getUserServices(serviceId) {
   }
 }
 
+export async function getUserOrders(accountId) {
+  try {
+    return await window.marketplaceContract.get_user_orders({
+      account_id: accountId,
+    });
+  } catch (e) {
+    let finalErrorMsg = getErrMsg(e);
+    //toast.error(finalErrorMsg);
+    console.log(e);
+    return null;
+  }
+}
+
 export async function getServiceById(id) {
   try {
     return await window.marketplaceContract.get_service_by_id({
@@ -379,11 +420,11 @@ export async function getUser(accountId) {
   }
 }
 
-export async function reclaimDispute(serviceId, proves) {
+export async function reclaimDispute(orderId, proves) {
   try {
     let amt = utils.format.parseNearAmount("0.1");
     return await window.marketplaceContract.reclaim_dispute(
-      { service_id: serviceId, proves: proves },
+      { order_id: orderId, proves: proves },
       "300000000000000",
       amt
     );
@@ -552,7 +593,7 @@ export async function ftTransferCallJOBS(amount) {
       {
         receiver_id: window.marketplaceContract.contractId,
         amount: amount,
-        msg: "empty",
+        msg: "",
       },
       "300000000000000",
       "1"
@@ -595,7 +636,7 @@ export async function ftTransferCallUSDC(amount) {
     JSON.stringify({
       receiver_id: marketplaceConfig.contractName,
       amount: amount,
-      msg: "empty",
+      msg: "",
     })
   );
   try {
@@ -603,7 +644,7 @@ export async function ftTransferCallUSDC(amount) {
       {
         receiver_id: marketplaceConfig.contractName,
         amount: amount,
-        msg: "empty",
+        msg: "",
       },
       "300000000000000",
       "1"
@@ -22,22 +22,23 @@ export default function MyChats() {
 
   useEffect(async () => {
     if (salasDoc) {
-      let services = [];
+      let orders = [];
 
+      // Cada sala es de una orden: servicio, profesional y comprador.
       salasDoc.forEach((doc) => {
         const id = doc.id.split("&");
 
         if (window.accountId == id[1] || window.accountId == id[2]) {
-          const service = {
-            id: Number(id[0]),
+          const order = {
+            service_id: Number(id[0]),
             creator_id: id[1],
-            actual_owner: id[2],
+            buyer_id: id[2],
           };
-          services.push(service);
+          orders.push(order);
         }
       });
 
-      setSalas(services);
+      setSalas(orders);
     }
   }, [salasDoc]);
 
@@ -59,14 +60,14 @@ export default function MyChats() {
                         : "text-[#A5A2B8] text-left py-4 pl-6 w-full pr-8 border-b-2 transition ease-in-out hover:text-[#352E5B] duration-300"
                     }
                   >
-                    <div className="">{`Service ID ${v.id}`}</div>
+                    <div className="">{`Service ID ${v.service_id}`}</div>
                   </button>
                 </div>
               );
             })}
           </div>
           <div className="w-full">
-            <Chat service={salas[selectedSala]} />
+            <Chat order={salas[selectedSala]} />
           </div>
         </div>
       ) : (
@@ -4,11 +4,23 @@ import ServicesCard from "../components/ServicesCard";
 import CreateServiceDialog from "../components/CreateServiceDialog";
 import SkeletonLoaderService from "../components/SkeletonLoaderService";
 
-import { getUserServices } from "../utils";
+import { getServiceById, getUserOrders, getUserServices } from "../utils";
 import ServicesFilter from "../components/ServicesFilter";
 
+// Las categorias se guardan como JSON, se descartan los servicios con formato incorrecto.
+const parseCategories = (service) => {
+  try {
+    service.metadata.categories = JSON.parse(service.metadata.categories);
+    return true;
+  } catch (e) {
+    console.log("La categoria", service.id, "no tiene el formato correcto");
+    return false;
+  }
+};
+
 export default function MyServices() {
   let [services, setServices] = useState([]);
+  let [orders, setOrders] = useState([]);
   let [loading, setLoading] = useState(true);
   let [isOpen, setIsOpen] = useState(false);
 
@@ -16,23 +28,33 @@ export default function MyServices() {
     const foo = async () => {
       const _services = await getUserServices();
       let finalServices = [];
-      for (let i = 0; i < _services.length; i++) {
-        try {
-          _services[i].metadata.categories = JSON.parse(
-            _services[i].metadata.categories
-          );
+      for (let i = 0; i < (_services || []).length; i++) {
+        if (parseCategories(_services[i])) {
           finalServices.push(_services[i]);
-        } catch (e) {
-          console.log(
-            "La categoria",
-            _services[i].id,
-            "no tiene el formato correcto"
-          );
         }
       }
 
       setServices(finalServices);
 
+      // Ordenes compradas y vendidas, con el servicio de cada una.
+      const _orders = (await getUserOrders(window.accountId)) || [];
+      let servicesById = {};
+      let finalOrders = [];
+      for (let i = 0; i < _orders.length; i++) {
+        const id = _orders[i].service_id;
+        if (id == null) {
+          continue;
+        }
+        if (!(id in servicesById)) {
+          const s = await getServiceById(id);
+          servicesById[id] = s && parseCategories(s) ? s : null;
+        }
+        if (servicesById[id]) {
+          finalOrders.push({ order: _orders[i], service: servicesById[id] });
+        }
+      }
+      setOrders(finalOrders);
+
       setLoading(false);
     };
 
@@ -115,15 +137,13 @@ export default function MyServices() {
                 </div>
                 {services.length ? (
                   <>
-                    {services
-                      .filter((v) => !v.sold)
-                      .map((v, i) => {
-                        return (
-                          <div key={i} className="my-6">
-                            <ServicesCard service={v} />
-                          </div>
-                        );
-                      })}
+                    {services.map((v, i) => {
+                      return (
+                        <div key={i} className="my-6">
+                          <ServicesCard service={v} />
+                        </div>
+                      );
+                    })}
                   </>
                 ) : (
                   <>
@@ -137,16 +157,14 @@ export default function MyServices() {
                 <div className="text-xl text-center font-bold text-gray-800">
                 Acquired services
                 </div>
-                {services.length ? (
+                {orders.some((v) => v.order.buyer_id == window.accountId) ? (
                   <>
-                    {services
-                      .filter(
-                        (v) => v.actual_owner == window.accountId && v.sold
-                      )
+                    {orders
+                      .filter((v) => v.order.buyer_id == window.accountId)
                       .map((v, i) => {
                         return (
                           <div key={i} className="my-6">
-                            <ServicesCard service={v} />
+                            <ServicesCard service={v.service} order={v.order} />
                           </div>
                         );
                       })}
@@ -163,16 +181,14 @@ export default function MyServices() {
                 <div className="text-xl font-bold text-center text-gray-800">
                   Services sold
                 </div>
-                {services.length ? (
+                {orders.some((v) => v.order.creator_id == window.accountId) ? (
                   <>
-                    {services
-                      .filter(
-                        (v) => v.actual_owner != window.accountId && v.sold
-                      )
+                    {orders
+                      .filter((v) => v.order.creator_id == window.accountId)
                       .map((v, i) => {
                         return (
                           <div key={i} className="my-6">
-                            <ServicesCard service={v} />
+                            <ServicesCard service={v.service} order={v.order} />
                           </div>
                         );
                       })}
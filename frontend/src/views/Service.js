@@ -1,12 +1,14 @@
 import React, { useEffect, useState } from "react";
 import { useParams, Link, useNavigate } from "react-router-dom";
 import { ImCross, ImCheckmark } from "react-icons/im";
-import { utils } from "near-api-js";
 
 import {
   buyService,
+  fromBaseUnits,
   getServiceById,
+  getTokenInfo,
   getUser,
+  getUserOrders,
   login,
   reclaimService,
   reclaimServiceTest,
@@ -23,14 +25,25 @@ import { useGlobalState } from "../state";
 import { TokenIcons } from "../components/TokenIcons";
 import Chat from "../components/Chat";
 
+// Estados en los que la orden sigue abierta entre el comprador y el profesional.
+const OPEN_STATUS = [
+  "Pending",
+  "InProgress",
+  "Delivered",
+  "DisputeRequested",
+  "OnDispute",
+];
+
 export default function Service() {
   const [isUserCreated] = useGlobalState("isUserCreated");
   const [service, setService] = useState();
+  const [order, setOrder] = useState(null);
   const [user, setUser] = useState(null);
   const [loading, setLoading] = useState(true);
   const [loadingReclaimService, setLoadingReclaimService] = useState(false);
   const [isOpen, setIsOpen] = useState(false);
   const [loadingBuyService, setLoadingBuyService] = useState(false);
+  const [decimals, setDecimals] = useState(null);
 
   const params = useParams();
   const navigate = useNavigate();
@@ -45,6 +58,27 @@ export default function Service() {
       if (s) {
         setService(s);
         loadingService = false;
+
+        let tokenInfo = await getTokenInfo(s.metadata.token);
+        if (tokenInfo) {
+          setDecimals(tokenInfo.decimals);
+        }
+      }
+
+      // Orden del usuario sobre este servicio, como comprador o como profesional.
+      // Se prioriza la ultima abierta, si no hay se toma la ultima cerrada.
+      if (window.accountId) {
+        let orders = await getUserOrders(window.accountId);
+        let own = (orders || []).filter(
+          (o) =>
+            o.service_id == s.id &&
+            (o.buyer_id == window.accountId || o.creator_id == window.accountId)
+        );
+        let open = own.filter((o) => OPEN_STATUS.includes(o.status));
+        let last = open.length > 0 ? open[open.length - 1] : own[own.length - 1];
+        if (last) {
+          setOrder(last);
+        }
       }
 
       let user = await getUser(s.creator_id);
@@ -62,28 +96,22 @@ export default function Service() {
     foo();
   }, []);
 
+  const isCreator = () => service.creator_id == window.accountId;
+
+  const isBuyer = () => order != null && order.buyer_id == window.accountId;
+
+  const hasStatus = (...status) => order != null && status.includes(order.status);
+
   const showChat = () => {
-    if (
-      (window.accountId == service.creator_id ||
-        window.accountId == service.actual_owner) &&
-      service.creator_id != service.actual_owner
-    ) {
-      return true;
-    }
-
-    return false;
+    return order != null;
   };
 
   const handleBuyService = async () => {
     setLoadingBuyService(true);
-    if (service.metadata.token != "near") {
-      await buyService(service.id, service.metadata.price);
-    } else {
-      const amount = utils.format.parseNearAmount(
-        String(service.metadata.price)
-      );
-      await buyService(service.id, amount);
-    }
+    // El precio ya esta en la unidad minima del token. El NEAR se adjunta tal cual,
+    // los FT se cobran desde la wallet del Marketplace.
+    const deposit = service.metadata.token == "near" ? service.metadata.price : "0";
+    await buyService(service.id, deposit);
 
     setLoadingBuyService(false);
     return;
@@ -106,64 +134,29 @@ export default function Service() {
     );
   };
 
-  const timeLeftService = (sold_moment) => {
-    // let s = new Date(Math.round((sold_moment) / 1000000)) - clock
-    let s = new Date(Math.round(sold_moment / 1000000));
-    s.setDate(s.getDate() + service.duration);
-    return (
-      s.getDate() +
-      "/" +
-      (s.getMonth() + 1) +
-      "/" +
-      s.getFullYear() +
-      "  (" +
-      s.getHours() +
-      ":" +
-      s.getMinutes() +
-      ")"
-    );
-  };
-
   const handleReclainService = async () => {
-    let now = new Date().getTime();
     setLoadingReclaimService(true);
-    if (now >= getReclaimDate()) {
-      await reclaimService(service.id);
-      // location.reload();
-      console.log(service.id, "Hora correcta");
-    } else {
-      await reclaimService(service.id);
-      location.reload();
-    }
-  };
-
-  const getReclaimDate = () => {
-    let reclaimMoment = new Date(Math.round(service.buy_moment / 1000000));
-    return reclaimMoment.setDate(reclaimMoment.getDate() + service.duration);
+    await reclaimService(order.id);
+    setLoadingReclaimService(false);
+    location.reload();
   };
 
   return (
     <div className="">
       {service ? (
-        service.actual_owner == window.accountId &&
-        service.creator_id == window.accountId &&
-        !service.sold &&
-        isUserCreated ? (
+        isCreator() && isUserCreated ? (
           <CreateServiceDialog
             isOpen={isOpen}
             closeModal={closeModal}
             openModal={openModal}
             service={service}
           />
-        ) : service.actual_owner == window.accountId &&
-          service.creator_id != window.accountId &&
-          service.sold &&
-          isUserCreated ? (
+        ) : isBuyer() && hasStatus("InProgress", "Delivered") && isUserCreated ? (
           <CreateDisputeDialog
             isOpen={isOpen}
             closeModal={closeModal}
             openModal={openModal}
-            serviceId={service.id}
+            orderId={order ? order.id : null}
           />
         ) : (
           <></>
@@ -196,9 +189,9 @@ export default function Service() {
               >
                 Login
               </button>
-            ) : (service.actual_owner != window.accountId ||
-                service.creator_id != window.accountId) &&
-              !service.sold &&
+            ) : !isCreator() &&
+              service.on_sale &&
+              !(isBuyer() && hasStatus(...OPEN_STATUS)) &&
               isUserCreated ? (
               <button
                 onClick={handleBuyService}
@@ -223,10 +216,7 @@ export default function Service() {
                   <></>
                 )}
               </button>
-            ) : service.actual_owner == window.accountId &&
-              service.creator_id == window.accountId &&
-              !service.sold &&
-              isUserCreated ? (
+            ) : isCreator() && isUserCreated ? (
               <div className="flex flex-row justify-between">
                 <div className="flex">
                   <button
@@ -238,12 +228,21 @@ export default function Service() {
                   {/* <button className="uppercase py-2 px-4 rounded-lg bg-red-400 border-transparent text-white text-md mr-4">
                     Eliminar servicio
                   </button> */}
+                  {hasStatus("Delivered") ? (
+                    <button
+                      onClick={handleReclainService}
+                      disabled={loadingReclaimService}
+                      className="uppercase py-2 px-4 rounded-lg bg-green-600 border-transparent text-white text-md mr-4 transition ease-in-out hover:scale-105 hover:-translate-y-0.5 duration-300 shadow-lg"
+                    >
+                      Claim payment!
+                    </button>
+                  ) : (
+                    <></>
+                  )}
                 </div>
               </div>
-            ) : service.actual_owner == window.accountId &&
-              service.creator_id != window.accountId &&
-              service.sold &&
-              !service.on_dispute &&
+            ) : isBuyer() &&
+              hasStatus("InProgress", "Delivered") &&
               isUserCreated ? (
               <div className="flex justify-end">
                 <button
@@ -253,20 +252,6 @@ export default function Service() {
                   Create dispute!
                 </button>
               </div>
-            ) : service.actual_owner != window.accountId &&
-              service.creator_id == window.accountId &&
-              service.sold &&
-              !service.on_dispute &&
-              isUserCreated ? (
-              <div>
-                <button
-                  onClick={handleReclainService}
-                  disabled={loadingReclaimService}
-                  className="uppercase py-2 px-4 rounded-lg bg-green-600 border-transparent text-white text-md mr-4 transition ease-in-out hover:scale-105 hover:-translate-y-0.5 duration-300 shadow-lg"
-                >
-                  Claim payment!
-                </button>
-              </div>
             ) : (
               <></>
             )}
@@ -308,22 +293,28 @@ export default function Service() {
                     <span className="font-semibold">Creador: </span>
                     {service.creator_id}
                   </div>
-                  <div
-                    className="hover:cursor-pointer"
-                    onClick={() => {
-                      navigate(`/profile/${service.actual_owner}`, {
-                        replace: true,
-                      });
-                    }}
-                  >
-                    <span className="font-semibold">Dueño: </span>
-                    {service.actual_owner}
-                  </div>
+                  {order ? (
+                    <div
+                      className="hover:cursor-pointer"
+                      onClick={() => {
+                        navigate(`/profile/${order.buyer_id}`, {
+                          replace: true,
+                        });
+                      }}
+                    >
+                      <span className="font-semibold">Comprador: </span>
+                      {order.buyer_id}
+                    </div>
+                  ) : (
+                    <></>
+                  )}
                 </div>
 
                 <div className="text-lg flex items-center">
                   <span className="font-semibold">Price</span>:{" "}
-                  {service.metadata.price}
+                  {decimals != null
+                    ? fromBaseUnits(service.metadata.price, decimals)
+                    : "..."}
                   <img
                     className="w-[26px] ml-1"
                     src={
@@ -340,17 +331,6 @@ export default function Service() {
                     <span className="font-light"> {service.duration} days</span>
                   </div>
 
-                  <div className="flex items-center mr-2">
-                    Sold:
-                    <span className="font-light mx-1">
-                      {service.sold ? (
-                        <ImCheckmark color="green" />
-                      ) : (
-                        <ImCross color="red" />
-                      )}
-                    </span>
-                  </div>
-
                   <div className="flex items-center mr-2">
                     On Sale:
                     <span className="font-light mx-1">
@@ -362,26 +342,26 @@ export default function Service() {
                     </span>
                   </div>
 
-                  <div className="flex items-center mr-2">
-                    On Dispute:
-                    <span className="font-light mx-1">
-                      {service.on_dispute ? (
-                        <ImCheckmark color="green" />
-                      ) : (
-                        <ImCross color="red" />
-                      )}
-                    </span>
-                  </div>
+                  {order ? (
+                    <div className="mr-2">
+                      Order:
+                      <span className="font-light"> {order.status}</span>
+                    </div>
+                  ) : (
+                    <></>
+                  )}
                 </div>
               </div>
-              {service.sold && (
+              {order && (
                 <div className="mt-6 font-medium flex">
                   <div className="text font-semibold text-gray-800 mb-2 mr-3">
-                    Momento de compra {dateToString(service.buy_moment)}
-                  </div>
-                  <div className="text font-semibold text-gray-800">
-                    Terminara el {timeLeftService(service.buy_moment)}
+                    Momento de compra {dateToString(order.created_at)}
                   </div>
+                  {order.status != "Pending" && (
+                    <div className="text font-semibold text-gray-800">
+                      Terminara el {dateToString(order.deadline)}
+                    </div>
+                  )}
                 </div>
               )}
             </div>
@@ -391,7 +371,7 @@ export default function Service() {
                 <div className="text-2xl text-center font-bold text-gray-800 mb-4">
                   Chat
                 </div>
-                <Chat service={service}/>
+                <Chat order={order} />
               </div>
             ) : (
               <></>